@@ -2,40 +2,130 @@ use rubato::VecResampler;
 use symphonia::core::audio::{AudioBufferRef, Signal};
 use symphonia::core::codecs::{CODEC_TYPE_NULL, DecoderOptions};
 use symphonia::core::formats::FormatOptions;
-use symphonia::core::io::MediaSourceStream;
+use symphonia::core::io::{MediaSource, MediaSourceStream};
 use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::Hint;
 
 use super::*;
+use filters::Filters;
 use std::fs::File;
-use std::path::PathBuf;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+/// Sample rate whisper expects
+const TARGET_SAMPLE_RATE: u32 = 16000;
+
+/// Resampling quality, trading accuracy for speed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Quality {
+    /// Cubic polynomial interpolation
+    Fast,
+    /// Short sinc filter
+    Balanced,
+    /// Long sinc filter
+    #[default]
+    Best,
+}
+
+impl Quality {
+    fn name(self) -> &'static str {
+        match self {
+            Quality::Fast => "fast",
+            Quality::Balanced => "balanced",
+            Quality::Best => "best",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AudioOptions {
+    /// Resampling quality, unused when the input is already 16 kHz
+    pub quality: Quality,
+    /// Preprocessing applied after resampling
+    pub filters: Filters,
+    /// Write the processed 16 kHz audio to `debug/<name>.wav`
+    pub dump_wav: bool,
+}
+
+impl AudioOptions {
+    /// Stable encoding of the options changing what whisper receives, part of the transcript
+    /// cache key
+    pub fn cache_key(&self) -> String {
+        // Destructured so a new option can't be left out
+        let Self {
+            quality,
+            filters,
+            dump_wav: _,
+        } = self;
+        format!("quality={};{}", quality.name(), filters.cache_key())
+    }
+}
 
 /// Read audio to Vec<i16>
 /// # Notes
 /// - if mp3, convert to wav
 /// - if wav, validate it
-pub fn run(path: &PathBuf) -> Result<Vec<f32>> {
+pub fn run(path: &Path, options: &AudioOptions) -> Result<Vec<f32>> {
     let audio_extension = path
         .extension()
         .and_then(|f| f.to_str())
         .unwrap_or_default();
-    match audio_extension {
-        "mp3" => transcode(&path, None),
+    let samples = decode(Box::new(File::open(path)?), audio_extension, options)?;
+
+    if options.dump_wav {
+        let name = path.file_stem().and_then(|f| f.to_str()).unwrap_or("audio");
+        let dump = dump_wav(name, &samples)?;
+        tracing::debug!("Processed audio written to {}", dump.display());
+    }
+
+    Ok(samples)
+}
+
+/// Same as [`run`], for audio already in memory (e.g. an upload)
+pub fn from_bytes(bytes: Vec<u8>, extension: &str, options: &AudioOptions) -> Result<Vec<f32>> {
+    decode(Box::new(Cursor::new(bytes)), extension, options)
+}
+
+fn decode(src: Box<dyn MediaSource>, extension: &str, options: &AudioOptions) -> Result<Vec<f32>> {
+    match extension {
+        "mp3" => transcode(src, None, options),
         "wav" => {
             let mut hint = Hint::default();
             hint.with_extension("wav");
-            transcode(&path, Some(&hint))
+            transcode(src, Some(&hint), options)
         }
-        ext => Err(Error::Unsupoort(ext.to_string())),
+        ext => Err(Error::Unsupported(ext.to_string())),
     }
 }
 
-fn transcode(input: &PathBuf, hint: Option<&Hint>) -> Result<Vec<f32>> {
-    // Open audio
-    let src = File::open(&input)?;
+/// Write 16 kHz mono samples as a 16-bit WAV in `debug/`
+fn dump_wav(name: &str, samples: &[f32]) -> Result<PathBuf> {
+    let dir = PathBuf::from(format!("{}/debug/", env!("CARGO_MANIFEST_DIR")));
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{name}.wav"));
 
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: TARGET_SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(&path, spec)?;
+    for sample in samples {
+        writer.write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)?;
+    }
+    writer.finalize()?;
+
+    Ok(path)
+}
+
+fn transcode(
+    src: Box<dyn MediaSource>,
+    hint: Option<&Hint>,
+    options: &AudioOptions,
+) -> Result<Vec<f32>> {
     // Create media source stream
-    let mss = MediaSourceStream::new(Box::new(src), Default::default());
+    let mss = MediaSourceStream::new(src, Default::default());
 
     // Hint
     let hint = match hint {
@@ -70,115 +160,184 @@ fn transcode(input: &PathBuf, hint: Option<&Hint>) -> Result<Vec<f32>> {
 
     // Get the original sample rate
     let original_sample_rate = track.codec_params.sample_rate.unwrap_or(44100);
-    let target_sample_rate = 16000;
+    let target_sample_rate = TARGET_SAMPLE_RATE;
 
     // Collect all samples first
     let mut original_samples = Vec::new();
 
     while let Ok(packet) = format.next_packet() {
-        if packet.track_id() == track_id {
-            if let Ok(decoded) = decoder.decode(&packet) {
-                match decoded {
-                    AudioBufferRef::U8(buf) => {
-                        original_samples.extend(
-                            buf.chan(0)
-                                .iter()
-                                .map(|&s| ((s as i16 - 128) * 256) as f32 / i16::MAX as f32),
-                        );
-                    }
-                    AudioBufferRef::U16(_buf) => {
-                        return Err(Error::UnsupportedSampleFormat);
-                    }
-                    AudioBufferRef::U24(_buf) => {
-                        return Err(Error::UnsupportedSampleFormat);
-                    }
-                    AudioBufferRef::U32(buf) => {
-                        original_samples.extend(
-                            buf.chan(0)
-                                .iter()
-                                .map(|&s| ((s >> 16) as i16) as f32 / i16::MAX as f32),
-                        );
-                    }
-                    AudioBufferRef::S8(buf) => {
-                        original_samples.extend(
-                            buf.chan(0)
-                                .iter()
-                                .map(|&s| (s as i16 * 256) as f32 / i16::MAX as f32),
-                        );
-                    }
-                    AudioBufferRef::S16(buf) => {
-                        original_samples
-                            .extend(buf.chan(0).iter().map(|&s| s as f32 / i16::MAX as f32));
-                    }
-                    AudioBufferRef::S24(_buf) => {
-                        return Err(Error::UnsupportedSampleFormat);
-                    }
-                    AudioBufferRef::S32(buf) => {
-                        original_samples.extend(
-                            buf.chan(0)
-                                .iter()
-                                .map(|&s| ((s >> 16) as i16) as f32 / i16::MAX as f32),
-                        );
-                    }
-                    AudioBufferRef::F32(buf) => {
-                        original_samples.extend(buf.chan(0).iter().copied());
-                    }
-                    AudioBufferRef::F64(buf) => {
-                        original_samples.extend(buf.chan(0).iter().map(|&s| s as f32));
-                    }
+        if packet.track_id() == track_id
+            && let Ok(decoded) = decoder.decode(&packet)
+        {
+            match decoded {
+                AudioBufferRef::U8(buf) => {
+                    original_samples.extend(
+                        buf.chan(0)
+                            .iter()
+                            .map(|&s| ((s as i16 - 128) * 256) as f32 / i16::MAX as f32),
+                    );
+                }
+                AudioBufferRef::U16(_buf) => {
+                    return Err(Error::UnsupportedSampleFormat);
+                }
+                AudioBufferRef::U24(_buf) => {
+                    return Err(Error::UnsupportedSampleFormat);
+                }
+                AudioBufferRef::U32(buf) => {
+                    original_samples.extend(
+                        buf.chan(0)
+                            .iter()
+                            .map(|&s| ((s >> 16) as i16) as f32 / i16::MAX as f32),
+                    );
+                }
+                AudioBufferRef::S8(buf) => {
+                    original_samples.extend(
+                        buf.chan(0)
+                            .iter()
+                            .map(|&s| (s as i16 * 256) as f32 / i16::MAX as f32),
+                    );
+                }
+                AudioBufferRef::S16(buf) => {
+                    original_samples
+                        .extend(buf.chan(0).iter().map(|&s| s as f32 / i16::MAX as f32));
+                }
+                AudioBufferRef::S24(_buf) => {
+                    return Err(Error::UnsupportedSampleFormat);
+                }
+                AudioBufferRef::S32(buf) => {
+                    original_samples.extend(
+                        buf.chan(0)
+                            .iter()
+                            .map(|&s| ((s >> 16) as i16) as f32 / i16::MAX as f32),
+                    );
+                }
+                AudioBufferRef::F32(buf) => {
+                    original_samples.extend(buf.chan(0).iter().copied());
+                }
+                AudioBufferRef::F64(buf) => {
+                    original_samples.extend(buf.chan(0).iter().map(|&s| s as f32));
                 }
             }
         }
     }
 
-    // Set up the resampler
-    let params = rubato::SincInterpolationParameters {
-        sinc_len: 256,
-        f_cutoff: 0.95,
-        interpolation: rubato::SincInterpolationType::Linear,
-        oversampling_factor: 256,
-        window: rubato::WindowFunction::BlackmanHarris2,
+    let mut output_samples = resample(
+        original_samples,
+        original_sample_rate,
+        target_sample_rate,
+        options.quality,
+    )?;
+
+    options.filters.apply(&mut output_samples);
+
+    Ok(output_samples)
+}
+
+/// Resample mono audio to `to` Hz, trimmed to exactly the resampled length
+fn resample(samples: Vec<f32>, from: u32, to: u32, quality: Quality) -> Result<Vec<f32>> {
+    // Already at the target rate, nothing to do
+    if from == to {
+        return Ok(samples);
+    }
+
+    let ratio = to as f64 / from as f64;
+    let chunk_size = 1024;
+
+    let mut resampler: Box<dyn VecResampler<f32>> = match quality {
+        Quality::Fast => Box::new(rubato::FastFixedIn::<f32>::new(
+            ratio,
+            1.0,
+            rubato::PolynomialDegree::Cubic,
+            chunk_size,
+            1, // channels
+        )?),
+        Quality::Balanced => Box::new(rubato::SincFixedIn::<f32>::new(
+            ratio,
+            1.0,
+            rubato::SincInterpolationParameters {
+                sinc_len: 64,
+                f_cutoff: 0.91,
+                interpolation: rubato::SincInterpolationType::Linear,
+                oversampling_factor: 128,
+                window: rubato::WindowFunction::Blackman2,
+            },
+            chunk_size,
+            1, // channels
+        )?),
+        Quality::Best => Box::new(rubato::SincFixedIn::<f32>::new(
+            ratio,
+            1.0,
+            rubato::SincInterpolationParameters {
+                sinc_len: 256,
+                f_cutoff: 0.95,
+                interpolation: rubato::SincInterpolationType::Linear,
+                oversampling_factor: 256,
+                window: rubato::WindowFunction::BlackmanHarris2,
+            },
+            chunk_size,
+            1, // channels
+        )?),
     };
 
-    // Create a resampler with fixed input size
-    let mut resampler = rubato::SincFixedIn::<f32>::new(
-        target_sample_rate as f64 / original_sample_rate as f64,
-        2.0,
-        params,
-        1024, // chunk size
-        1,    // channels
-    )?;
+    // The resampler output starts `delay` frames late; trim that and the
+    // flushed tail so the result is exactly as long as the input
+    let expected = (samples.len() as f64 * ratio).ceil() as usize;
+    let delay = resampler.output_delay();
 
-    // Prepare input in the format expected by rubato (Vec<Vec<f64>>)
-    let chunk_size = resampler.input_frames_next();
-    let mut input_buffer = vec![Vec::new()];
+    let mut output_samples = Vec::with_capacity(expected + delay + chunk_size);
 
     // Process the audio in chunks
-    let mut output_samples = Vec::new();
+    let mut chunks = samples.chunks_exact(chunk_size);
+    for chunk in &mut chunks {
+        let output = resampler.process(&[chunk.to_vec()], None)?;
+        output_samples.extend_from_slice(&output[0]);
+    }
 
-    for chunk in original_samples.chunks(chunk_size) {
-        // Clear and refill the input buffer
-        input_buffer[0].clear();
-        input_buffer[0].extend_from_slice(chunk);
+    // The last, shorter chunk is zero-padded by rubato
+    let remainder = chunks.remainder();
+    if !remainder.is_empty() {
+        let output = resampler.process_partial(Some(&[remainder.to_vec()]), None)?;
+        output_samples.extend_from_slice(&output[0]);
+    }
 
-        // If the last chunk is smaller than chunk_size, pad with zeros
-        if chunk.len() < chunk_size {
-            input_buffer[0].resize(chunk_size, 0.0);
-        }
+    // Flush the frames still held back by the delay
+    while output_samples.len() < delay + expected {
+        let output = resampler.process_partial(None, None)?;
+        output_samples.extend_from_slice(&output[0]);
+    }
 
-        // Process the chunk
-        let output = resampler.process(&input_buffer, None)?;
+    output_samples.drain(..delay);
+    output_samples.truncate(expected);
 
-        // Collect the output
-        output_samples.extend(output[0].iter());
+    Ok(output_samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 44.1 kHz sine, its length not a multiple of the 1024-frame chunks
+    fn tone(len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| (i as f32 * 440.0 * 2.0 * std::f32::consts::PI / 44100.0).sin())
+            .collect()
     }
 
-    // Process any remaining frames
-    let output = resampler.process_partial(None, None)?;
+    #[test]
+    fn resample_keeps_exact_length() {
+        let samples = tone(44100 + 333);
+        let expected = (samples.len() as f64 * 16000.0 / 44100.0).ceil() as usize;
 
-    if !output.is_empty() && !output[0].is_empty() {
-        output_samples.extend(output[0].iter());
+        for quality in [Quality::Fast, Quality::Balanced, Quality::Best] {
+            let output = resample(samples.clone(), 44100, 16000, quality).unwrap();
+            assert_eq!(output.len(), expected, "{quality:?}");
+        }
     }
 
-    Ok(output_samples)
+    #[test]
+    fn resample_skips_16_khz_input() {
+        let samples = tone(5000);
+        let output = resample(samples.clone(), 16000, 16000, Quality::Best).unwrap();
+        assert_eq!(output, samples);
+    }
 }
@@ -0,0 +1,332 @@
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use super::*;
+
+/// Sample rate of the audio handed to whisper
+const SAMPLE_RATE: f64 = 16000.0;
+
+/// One (audio, model) measurement
+pub struct Record {
+    pub audio: String,
+    pub model: String,
+    /// Audio duration in seconds
+    pub duration: f64,
+    /// Transcription time in seconds
+    pub elapsed: f64,
+    /// Peak resident memory in KiB, `None` if the platform doesn't report it
+    pub peak_memory: Option<u64>,
+    pub segments: usize,
+    /// Word error rate against `audio/<name>.txt`, if present
+    pub wer: Option<f64>,
+}
+
+impl Record {
+    /// Real-time factor: processing time divided by audio duration
+    pub fn rtf(&self) -> f64 {
+        if self.duration > 0.0 {
+            self.elapsed / self.duration
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Transcribe every audio file with every model and collect the measurements
+pub fn run(config: &Config) -> Result<Vec<Record>> {
+    // Decode every audio file once, up front
+    let mut audios = Vec::new();
+    for audio in &config.audio {
+        let audio_name = file_name(audio);
+        match audio::run(audio, &config.options.audio).map_err(Error::audio(audio)) {
+            Ok(samples) => audios.push((audio, audio_name, samples, reference(audio))),
+            Err(e) => tracing::error!("{e}"),
+        }
+    }
+
+    let mut records = Vec::new();
+
+    // Load one model at a time so its memory is measured on its own
+    for ggml in &config.gglm {
+        let ggml_name = file_name(ggml);
+        // A bad model file only costs its own rows
+        let model = match whisper::create_model(ggml).map_err(Error::model(ggml)) {
+            Ok(model) => model,
+            Err(e) => {
+                tracing::warn!("Skipping {ggml_name}: {e}");
+                continue;
+            }
+        };
+
+        for (audio, audio_name, samples, reference) in &audios {
+            tracing::info!("Benchmarking {} with {}", audio_name, ggml_name);
+
+            memory::reset_peak();
+            let start = Instant::now();
+
+            let transcribe = match whisper::run(&model, samples) {
+                Ok(transcribe) => transcribe,
+                Err(e) => {
+                    tracing::error!("{}", Error::transcribe(*audio, ggml)(e));
+                    continue;
+                }
+            };
+
+            let elapsed = start.elapsed().as_secs_f64();
+            let peak_memory = memory::peak();
+
+            let wer = reference.as_deref().map(|reference| {
+                let hypothesis = transcribe
+                    .iter()
+                    .map(|segment| segment.text.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                word_error_rate(reference, &hypothesis)
+            });
+
+            records.push(Record {
+                audio: audio_name.clone(),
+                model: ggml_name.clone(),
+                duration: samples.len() as f64 / SAMPLE_RATE,
+                elapsed,
+                peak_memory,
+                segments: transcribe.len(),
+                wer,
+            });
+        }
+    }
+
+    Ok(records)
+}
+
+/// Render the records as an aligned text table
+pub fn table(records: &[Record]) -> String {
+    let header = [
+        "audio", "model", "duration", "elapsed", "rtf", "peak_mem", "segments", "wer",
+    ];
+
+    let rows = records
+        .iter()
+        .map(|r| {
+            [
+                r.audio.clone(),
+                r.model.clone(),
+                format!("{:.2}s", r.duration),
+                format!("{:.2}s", r.elapsed),
+                format!("{:.3}", r.rtf()),
+                r.peak_memory
+                    .map(|kib| format!("{:.1}MiB", kib as f64 / 1024.0))
+                    .unwrap_or_else(|| "-".to_string()),
+                r.segments.to_string(),
+                r.wer
+                    .map(|wer| format!("{:.2}%", wer * 100.0))
+                    .unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    // Column widths
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let mut out = String::new();
+    let mut push_row = |cells: &[&str]| {
+        let line = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join(" | ");
+        let _ = writeln!(out, "{}", line.trim_end());
+    };
+
+    push_row(&header);
+    let separator = widths.map(|w| "-".repeat(w));
+    push_row(&separator.each_ref().map(String::as_str));
+    for row in &rows {
+        push_row(&row.each_ref().map(String::as_str));
+    }
+
+    out
+}
+
+/// Render the records as CSV, with raw numbers for spreadsheets
+pub fn csv(records: &[Record]) -> String {
+    let mut out =
+        String::from("audio,model,duration_s,elapsed_s,rtf,peak_memory_kib,segments,wer\n");
+
+    for r in records {
+        let _ = writeln!(
+            out,
+            "{},{},{:.3},{:.3},{:.4},{},{},{}",
+            csv_field(&r.audio),
+            csv_field(&r.model),
+            r.duration,
+            r.elapsed,
+            r.rtf(),
+            r.peak_memory.map(|m| m.to_string()).unwrap_or_default(),
+            r.segments,
+            r.wer.map(|w| format!("{w:.4}")).unwrap_or_default(),
+        );
+    }
+
+    out
+}
+
+/// Quote a CSV field if it contains a separator, quote or newline
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Read the reference transcript `audio/<name>.txt` next to the audio file
+fn reference(audio: &Path) -> Option<String> {
+    std::fs::read_to_string(audio.with_extension("txt")).ok()
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .and_then(|f| f.to_str())
+        .unwrap_or_else(|| path.to_str().unwrap_or("Invalid"))
+        .to_string()
+}
+
+/// Word error rate: word-level edit distance divided by the reference length
+///
+/// Both texts are lowercased and stripped of punctuation before comparison
+pub fn word_error_rate(reference: &str, hypothesis: &str) -> f64 {
+    let reference = words(reference);
+    let hypothesis = words(hypothesis);
+
+    if reference.is_empty() {
+        return if hypothesis.is_empty() { 0.0 } else { 1.0 };
+    }
+
+    // Levenshtein distance over words, keeping a single row
+    let mut row = (0..=hypothesis.len()).collect::<Vec<_>>();
+    for (i, r) in reference.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, h) in hypothesis.iter().enumerate() {
+            let substitution = diagonal + usize::from(r != h);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+
+    row[hypothesis.len()] as f64 / reference.len() as f64
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric() || *c == '\'')
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Where the CSV report is written
+pub fn csv_path() -> PathBuf {
+    PathBuf::from(format!("{}/benchmark.csv", env!("CARGO_MANIFEST_DIR")))
+}
+
+/// Peak resident memory, read from procfs
+///
+/// Only Linux reports it; elsewhere the column is left empty
+mod memory {
+    /// Reset the kernel's peak RSS counter (`VmHWM`) to the current RSS
+    pub fn reset_peak() {
+        let _ = std::fs::write("/proc/self/clear_refs", "5");
+    }
+
+    /// Peak RSS in KiB since the last reset
+    pub fn peak() -> Option<u64> {
+        let status = std::fs::read_to_string("/proc/self/status").ok()?;
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix("VmHWM:"))
+            .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(audio: &str, wer: Option<f64>) -> Record {
+        Record {
+            audio: audio.to_string(),
+            model: "ggml-base.bin".to_string(),
+            duration: 10.0,
+            elapsed: 2.5,
+            peak_memory: None,
+            segments: 3,
+            wer,
+        }
+    }
+
+    #[test]
+    fn wer_counts_substitutions() {
+        assert_eq!(
+            word_error_rate("the cat sat down", "the dog sat down"),
+            0.25
+        );
+    }
+
+    #[test]
+    fn wer_counts_insertions() {
+        assert_eq!(word_error_rate("the cat sat", "the big cat sat"), 1.0 / 3.0);
+    }
+
+    #[test]
+    fn wer_counts_deletions() {
+        assert_eq!(word_error_rate("the cat sat down", "the cat"), 0.5);
+    }
+
+    #[test]
+    fn wer_with_empty_reference() {
+        assert_eq!(word_error_rate("", ""), 0.0);
+        assert_eq!(word_error_rate(" ... ", "hello"), 1.0);
+    }
+
+    #[test]
+    fn wer_ignores_punctuation_and_case() {
+        assert_eq!(
+            word_error_rate("Hello, world! It's me.", "hello world it's ME"),
+            0.0
+        );
+    }
+
+    #[test]
+    fn csv_field_quotes_when_needed() {
+        assert_eq!(csv_field("plain.wav"), "plain.wav");
+        assert_eq!(csv_field("a,b.wav"), "\"a,b.wav\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn table_aligns_columns() {
+        let table = table(&[record("a.wav", Some(0.125)), record("longer.wav", None)]);
+        let lines = table.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("audio      | model"));
+        assert!(lines[1].starts_with("---------- | -----"));
+        assert!(lines[2].starts_with("a.wav      | ggml-base.bin | 10.00s"));
+        assert!(lines[2].ends_with("| 0.250 | -        | 3        | 12.50%"));
+        assert!(lines[3].ends_with("| -"));
+    }
+}
@@ -0,0 +1,133 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use super::*;
+
+/// Whisper transcripts stored on disk, one JSON file per key
+///
+/// The key covers the audio content, the model file and everything that
+/// changes what whisper receives or how it decodes, so a hit is always
+/// the transcript a fresh run would produce
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    pub fn new() -> Self {
+        Self::in_dir(PathBuf::from(format!(
+            "{}/cache/",
+            env!("CARGO_MANIFEST_DIR")
+        )))
+    }
+
+    fn in_dir(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub fn key(
+        audio: &blake3::Hash,
+        model: &blake3::Hash,
+        options: &audio::AudioOptions,
+    ) -> String {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(audio.as_bytes());
+        hasher.update(model.as_bytes());
+        // One line each, so a value can't run into the next one
+        hasher.update(format!("{}\n", whisper::DECODING.cache_key()).as_bytes());
+        hasher.update(format!("{}\n", options.cache_key()).as_bytes());
+        hasher.finalize().to_hex().to_string()
+    }
+
+    pub fn load(&self, key: &str) -> Option<Vec<Segment>> {
+        let cached = std::fs::read_to_string(self.path(key)).ok()?;
+        serde_json::from_str(&cached).ok()
+    }
+
+    pub fn store(&self, key: &str, segments: &[Segment]) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(self.path(key), serde_json::to_vec(segments)?)?;
+        Ok(())
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.json"))
+    }
+}
+
+/// Hash a file's content
+pub fn hash_file(path: &Path) -> Result<blake3::Hash> {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_reader(File::open(path)?)?;
+    Ok(hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use audio::{AudioOptions, Quality};
+    use filters::{HighPass, Normalize};
+
+    fn cache(name: &str) -> Cache {
+        let dir = std::env::temp_dir().join(format!("speech2text-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        Cache::in_dir(dir)
+    }
+
+    fn segments() -> Vec<Segment> {
+        vec![Segment {
+            start: 0,
+            end: 150,
+            text: " Hello there".to_string(),
+            speaker: Some("SPEAKER_1".to_string()),
+        }]
+    }
+
+    #[test]
+    fn hit_and_miss() {
+        let cache = cache("hit-and-miss");
+        let (audio, model) = (blake3::hash(b"audio"), blake3::hash(b"model"));
+        let key = Cache::key(&audio, &model, &AudioOptions::default());
+
+        assert!(cache.load(&key).is_none());
+        cache.store(&key, &segments()).unwrap();
+        let loaded = cache.load(&key).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].text, " Hello there");
+        assert_eq!(loaded[0].speaker.as_deref(), Some("SPEAKER_1"));
+
+        let other = Cache::key(
+            &blake3::hash(b"other audio"),
+            &model,
+            &AudioOptions::default(),
+        );
+        assert!(cache.load(&other).is_none());
+    }
+
+    #[test]
+    fn key_changes_with_what_whisper_receives() {
+        let (audio, model) = (blake3::hash(b"audio"), blake3::hash(b"model"));
+        let options = AudioOptions::default();
+        let base = Cache::key(&audio, &model, &options);
+
+        let with = |change: fn(&mut AudioOptions)| {
+            let mut options = AudioOptions::default();
+            change(&mut options);
+            Cache::key(&audio, &model, &options)
+        };
+        let changed = [
+            Cache::key(&blake3::hash(b"other audio"), &model, &options),
+            Cache::key(&audio, &blake3::hash(b"other model"), &options),
+            with(|options| options.quality = Quality::Fast),
+            with(|options| options.filters.high_pass = Some(HighPass { cutoff: 80.0 })),
+            with(|options| options.filters.normalize = Some(Normalize::Peak(-1.0))),
+            with(|options| options.filters.normalize = Some(Normalize::Loudness(-1.0))),
+        ];
+        for (i, key) in changed.iter().enumerate() {
+            assert_ne!(*key, base, "change {i} kept the key");
+        }
+
+        // Only the debug output changes
+        assert_eq!(with(|options| options.dump_wav = true), base);
+        assert_eq!(Cache::key(&audio, &model, &options), base);
+    }
+}
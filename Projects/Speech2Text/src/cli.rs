@@ -0,0 +1,228 @@
+use std::net::SocketAddr;
+
+use super::*;
+use audio::Quality;
+use diarize::Diarization;
+use filters::{HighPass, NoiseGate, Normalize};
+
+const USAGE: &str = "\
+Usage: speech2text [COMMAND] [OPTIONS]
+
+Commands:
+  run                   Transcribe every audio file with every model (default)
+  bench                 Benchmark every model against every audio file
+  watch                 Transcribe new or modified files in `audio`
+  serve [ADDR]          Serve transcriptions over HTTP (default 127.0.0.1:3000)
+
+Options:
+  --quality <PRESET>        Resampling quality: `fast`, `balanced` or `best` (default)
+  --highpass <HZ>           High-pass filter cutoff, e.g. 80
+  --gate <DBFS>             Noise gate threshold, e.g. -45
+  --normalize <MODE>        `peak` or `loudness`
+  --normalize-target <DBFS> Normalization target (peak: -1, loudness: -20)
+  --dump-wav                Write the processed 16 kHz audio to `debug/`
+  --force                   Ignore cached transcripts and transcribe again
+  --diarize                 Label segments by speaker
+  --speakers <N>            Expected number of speakers (implies --diarize)";
+
+pub enum Command {
+    Run,
+    Bench,
+    Watch,
+    Serve(SocketAddr),
+}
+
+/// Options shared by every command
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    pub audio: audio::AudioOptions,
+    pub diarization: Option<Diarization>,
+    /// Bypass the transcript cache
+    pub force: bool,
+}
+
+pub struct Args {
+    pub command: Command,
+    pub options: Options,
+}
+
+impl Args {
+    pub fn parse() -> Result<Self> {
+        Self::parse_from(std::env::args().skip(1))
+    }
+
+    /// Parse `args`, without the program name
+    fn parse_from(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut args = args.into_iter().peekable();
+
+        let command = match args.peek().map(String::as_str) {
+            Some("run") => {
+                args.next();
+                Command::Run
+            }
+            Some("bench") => {
+                args.next();
+                Command::Bench
+            }
+            Some("watch") => {
+                args.next();
+                Command::Watch
+            }
+            Some("serve") => {
+                args.next();
+                let addr = args
+                    .next_if(|arg| !arg.starts_with("--"))
+                    .unwrap_or_else(|| "127.0.0.1:3000".to_string());
+                Command::Serve(addr.parse()?)
+            }
+            _ => Command::Run,
+        };
+
+        let mut options = Options::default();
+        let mut normalize = None;
+        let mut normalize_target = None;
+
+        while let Some(arg) = args.next() {
+            let filters = &mut options.audio.filters;
+            match arg.as_str() {
+                "--quality" => {
+                    options.audio.quality = match value(&arg, args.next())?.as_str() {
+                        "fast" => Quality::Fast,
+                        "balanced" => Quality::Balanced,
+                        "best" => Quality::Best,
+                        preset => {
+                            return Err(Error::InvalidArgument(format!(
+                                "--quality {preset}, expected `fast`, `balanced` or `best`"
+                            )));
+                        }
+                    }
+                }
+                "--highpass" => {
+                    let cutoff = number(&arg, args.next())?;
+                    if !(cutoff > 0.0 && cutoff < HighPass::MAX_CUTOFF) {
+                        return Err(Error::InvalidArgument(format!(
+                            "{arg} {cutoff}, expected a cutoff between 0 and {} Hz",
+                            HighPass::MAX_CUTOFF
+                        )));
+                    }
+                    filters.high_pass = Some(HighPass { cutoff });
+                }
+                "--gate" => {
+                    filters.noise_gate = Some(NoiseGate {
+                        threshold: number(&arg, args.next())?,
+                    })
+                }
+                "--normalize" => normalize = Some(value(&arg, args.next())?),
+                "--normalize-target" => normalize_target = Some(number(&arg, args.next())?),
+                "--dump-wav" => options.audio.dump_wav = true,
+                "--force" => options.force = true,
+                "--diarize" => {
+                    options.diarization.get_or_insert_with(Diarization::default);
+                }
+                "--speakers" => {
+                    let value = value(&arg, args.next())?;
+                    let speakers = value.parse().ok().filter(|&n| n > 0).ok_or_else(|| {
+                        Error::InvalidArgument(format!("{arg} {value}, expected a positive number"))
+                    })?;
+                    options
+                        .diarization
+                        .get_or_insert_with(Diarization::default)
+                        .speakers = Some(speakers);
+                }
+                "-h" | "--help" => {
+                    println!("{USAGE}");
+                    std::process::exit(0);
+                }
+                _ => return Err(Error::InvalidArgument(format!("{arg}\n\n{USAGE}"))),
+            }
+        }
+
+        options.audio.filters.normalize = match normalize.as_deref() {
+            Some("peak") => Some(Normalize::Peak(
+                normalize_target.unwrap_or(Normalize::PEAK_TARGET),
+            )),
+            Some("loudness") => Some(Normalize::Loudness(
+                normalize_target.unwrap_or(Normalize::LOUDNESS_TARGET),
+            )),
+            Some(mode) => {
+                return Err(Error::InvalidArgument(format!(
+                    "--normalize {mode}, expected `peak` or `loudness`"
+                )));
+            }
+            None => None,
+        };
+
+        Ok(Self { command, options })
+    }
+}
+
+fn value(flag: &str, value: Option<String>) -> Result<String> {
+    value.ok_or_else(|| Error::InvalidArgument(format!("{flag} expects a value")))
+}
+
+fn number(flag: &str, value: Option<String>) -> Result<f32> {
+    let value = self::value(flag, value)?;
+    value
+        .parse()
+        .map_err(|_| Error::InvalidArgument(format!("{flag} {value}, expected a number")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args> {
+        Args::parse_from(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn rejected(args: &[&str]) -> bool {
+        matches!(parse(args), Err(Error::InvalidArgument(_)))
+    }
+
+    #[test]
+    fn valid_flags() {
+        let args = parse(&[
+            "watch",
+            "--quality",
+            "fast",
+            "--highpass",
+            "80",
+            "--gate",
+            "-45",
+            "--normalize",
+            "loudness",
+            "--speakers",
+            "2",
+        ])
+        .unwrap();
+        assert!(matches!(args.command, Command::Watch));
+        let filters = &args.options.audio.filters;
+        assert_eq!(filters.high_pass.as_ref().unwrap().cutoff, 80.0);
+        assert_eq!(filters.noise_gate.as_ref().unwrap().threshold, -45.0);
+        assert!(matches!(
+            filters.normalize,
+            Some(Normalize::Loudness(Normalize::LOUDNESS_TARGET))
+        ));
+        assert_eq!(args.options.diarization.unwrap().speakers, Some(2));
+    }
+
+    #[test]
+    fn bad_values_are_rejected() {
+        assert!(rejected(&["--quality", "ultra"]));
+        assert!(rejected(&["--highpass", "0"]));
+        assert!(rejected(&["--highpass", "8000"]));
+        assert!(rejected(&["--highpass", "NaN"]));
+        assert!(rejected(&["--gate", "loud"]));
+        assert!(rejected(&["--normalize", "rms"]));
+        assert!(rejected(&[
+            "--normalize-target",
+            "-1dB",
+            "--normalize",
+            "peak"
+        ]));
+        assert!(rejected(&["--speakers", "0"]));
+        assert!(rejected(&["--speakers", "two"]));
+        assert!(rejected(&["--gate"]));
+        assert!(rejected(&["--frobnicate"]));
+    }
+}
@@ -7,10 +7,12 @@ use super::*;
 pub struct Config {
     pub gglm: Vec<PathBuf>,
     pub audio: Vec<PathBuf>,
+    pub audio_dir: PathBuf,
+    pub options: Options,
 }
 
 impl Config {
-    pub async fn new() -> Result<Self> {
+    pub async fn new(options: Options) -> Result<Self> {
         let gglm_path = PathBuf::from(format!("{}/ggml/", env!("CARGO_MANIFEST_DIR")));
         let audio_path = PathBuf::from(format!("{}/audio/", env!("CARGO_MANIFEST_DIR")));
 
@@ -21,14 +23,15 @@ impl Config {
             return Err(Error::GgmlNotFound);
         }
 
-        if audio.is_empty() {
-            return Err(Error::AudioNotFound);
-        }
-
         tracing::info!("Found {} gglm model", gglm.len());
         tracing::info!("Found {} audio", audio.len());
 
-        Ok(Self { gglm, audio })
+        Ok(Self {
+            gglm,
+            audio,
+            audio_dir: audio_path,
+            options,
+        })
     }
 }
 
@@ -48,5 +51,7 @@ async fn get_files(path: &PathBuf, ext: Vec<&str>) -> Result<Vec<PathBuf>> {
         }
     }
 
+    files.sort();
+
     Ok(files)
 }
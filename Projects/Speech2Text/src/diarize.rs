@@ -0,0 +1,450 @@
+use std::f32::consts::PI;
+
+use realfft::RealFftPlanner;
+
+use super::*;
+
+/// Sample rate of the audio handed to whisper
+const SAMPLE_RATE: usize = 16000;
+/// Samples per whisper timestamp unit (10ms)
+const SAMPLES_PER_TICK: usize = SAMPLE_RATE / 100;
+
+/// MFCC frame of 25ms, hop of 10ms
+const FRAME: usize = 400;
+const HOP: usize = 160;
+const FFT: usize = 512;
+const MEL_BANDS: usize = 26;
+/// Cepstral coefficients kept, without c0 (overall energy)
+const COEFFICIENTS: usize = 13;
+
+/// When the speaker count isn't given, a merge this many times further apart
+/// than the median of the merges before it joins two different speakers
+const SEPARATION: f32 = 2.0;
+
+#[derive(Debug, Clone, Default)]
+pub struct Diarization {
+    /// Expected number of speakers, estimated when `None`
+    pub speakers: Option<usize>,
+}
+
+impl Diarization {
+    /// Label each segment `SPEAKER_1`, `SPEAKER_2`, ... in order of first appearance
+    pub fn apply(&self, samples: &[f32], segments: &mut [Segment]) {
+        let mfcc = Mfcc::new();
+
+        // Segments too short for a single frame get no embedding
+        let embeddings = segments
+            .iter()
+            .map(|segment| {
+                let start = (segment.start.max(0) as usize * SAMPLES_PER_TICK).min(samples.len());
+                let end = (segment.end.max(0) as usize * SAMPLES_PER_TICK).min(samples.len());
+                mfcc.embedding(&samples[start..end.max(start)])
+            })
+            .collect::<Vec<_>>();
+
+        let indexed = embeddings
+            .iter()
+            .enumerate()
+            .filter_map(|(i, embedding)| embedding.as_ref().map(|e| (i, e.clone())))
+            .collect::<Vec<_>>();
+        if indexed.is_empty() {
+            return;
+        }
+
+        let vectors = standardize(indexed.iter().map(|(_, e)| e.clone()).collect());
+        let clusters = cluster(&vectors, self.speakers);
+
+        // Number speakers by first appearance
+        let mut labels = vec![None; segments.len()];
+        let mut order = Vec::new();
+        for ((i, _), cluster) in indexed.iter().zip(&clusters) {
+            let speaker = match order.iter().position(|c| c == cluster) {
+                Some(speaker) => speaker,
+                None => {
+                    order.push(*cluster);
+                    order.len() - 1
+                }
+            };
+            labels[*i] = Some(speaker + 1);
+        }
+
+        // Short segments take the previous speaker, or the next one at the start
+        let first = labels.iter().flatten().next().copied();
+        let mut previous = first;
+        for (segment, label) in segments.iter_mut().zip(labels) {
+            let speaker = label.or(previous);
+            previous = speaker;
+            segment.speaker = speaker.map(|speaker| format!("SPEAKER_{speaker}"));
+        }
+    }
+}
+
+/// Mel-frequency cepstral coefficients
+struct Mfcc {
+    fft: std::sync::Arc<dyn realfft::RealToComplex<f32>>,
+    window: Vec<f32>,
+    filters: Vec<Vec<f32>>,
+}
+
+impl Mfcc {
+    fn new() -> Self {
+        let fft = RealFftPlanner::<f32>::new().plan_fft_forward(FFT);
+        let window = (0..FRAME)
+            .map(|i| 0.54 - 0.46 * (2.0 * PI * i as f32 / (FRAME - 1) as f32).cos())
+            .collect();
+
+        Self {
+            fft,
+            window,
+            filters: mel_filters(),
+        }
+    }
+
+    /// Mean and standard deviation of the segment's MFCCs
+    fn embedding(&self, samples: &[f32]) -> Option<Vec<f32>> {
+        let frames = samples
+            .windows(FRAME)
+            .step_by(HOP)
+            .map(|frame| self.frame(frame))
+            .collect::<Vec<_>>();
+        if frames.is_empty() {
+            return None;
+        }
+
+        let n = frames.len() as f32;
+        let mut mean = vec![0.0; COEFFICIENTS];
+        for frame in &frames {
+            for (m, c) in mean.iter_mut().zip(frame) {
+                *m += c / n;
+            }
+        }
+        let mut std = vec![0.0; COEFFICIENTS];
+        for frame in &frames {
+            for ((s, c), m) in std.iter_mut().zip(frame).zip(&mean) {
+                *s += (c - m).powi(2) / n;
+            }
+        }
+
+        mean.extend(std.into_iter().map(f32::sqrt));
+        Some(mean)
+    }
+
+    fn frame(&self, frame: &[f32]) -> Vec<f32> {
+        let mut input = self.fft.make_input_vec();
+        for ((x, s), w) in input.iter_mut().zip(frame).zip(&self.window) {
+            *x = s * w;
+        }
+        let mut spectrum = self.fft.make_output_vec();
+        // Buffer sizes come from the plan, so this can't fail
+        let _ = self.fft.process(&mut input, &mut spectrum);
+
+        let power = spectrum.iter().map(|c| c.norm_sqr()).collect::<Vec<_>>();
+        let energies = self
+            .filters
+            .iter()
+            .map(|filter| {
+                let energy = filter.iter().zip(&power).map(|(f, p)| f * p).sum::<f32>();
+                energy.max(1e-10).ln()
+            })
+            .collect::<Vec<_>>();
+
+        // DCT-II, skipping c0
+        (1..=COEFFICIENTS)
+            .map(|k| {
+                energies
+                    .iter()
+                    .enumerate()
+                    .map(|(m, e)| e * (PI * k as f32 * (m as f32 + 0.5) / MEL_BANDS as f32).cos())
+                    .sum()
+            })
+            .collect()
+    }
+}
+
+/// Triangular mel filterbank between 20 Hz and 7600 Hz
+fn mel_filters() -> Vec<Vec<f32>> {
+    let mel = |hz: f32| 2595.0 * (1.0 + hz / 700.0).log10();
+    let hz = |mel: f32| 700.0 * (10.0_f32.powf(mel / 2595.0) - 1.0);
+
+    let (low, high) = (mel(20.0), mel(7600.0));
+    let bins = (0..MEL_BANDS + 2)
+        .map(|i| {
+            let frequency = hz(low + (high - low) * i as f32 / (MEL_BANDS + 1) as f32);
+            frequency * FFT as f32 / SAMPLE_RATE as f32
+        })
+        .collect::<Vec<_>>();
+
+    (0..MEL_BANDS)
+        .map(|m| {
+            let (left, center, right) = (bins[m], bins[m + 1], bins[m + 2]);
+            (0..FFT / 2 + 1)
+                .map(|bin| {
+                    let bin = bin as f32;
+                    if bin <= left || bin >= right {
+                        0.0
+                    } else if bin <= center {
+                        (bin - left) / (center - left)
+                    } else {
+                        (right - bin) / (right - center)
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Scale each dimension to zero mean and unit variance across segments
+fn standardize(mut vectors: Vec<Vec<f32>>) -> Vec<Vec<f32>> {
+    let n = vectors.len() as f32;
+    let dimensions = vectors[0].len();
+
+    for d in 0..dimensions {
+        let mean = vectors.iter().map(|v| v[d]).sum::<f32>() / n;
+        let std = (vectors.iter().map(|v| (v[d] - mean).powi(2)).sum::<f32>() / n).sqrt();
+        for v in &mut vectors {
+            v[d] = if std > 0.0 { (v[d] - mean) / std } else { 0.0 };
+        }
+    }
+
+    vectors
+}
+
+/// Average-linkage agglomerative clustering on cosine distance
+///
+/// Cuts the merge tree at `speakers` clusters. When no count is given, it's
+/// estimated from the merge distances of the same tree, see
+/// [`estimate_speakers`]. Returns a cluster id per vector.
+fn cluster(vectors: &[Vec<f32>], speakers: Option<usize>) -> Vec<usize> {
+    let n = vectors.len();
+
+    let mut distances = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        for j in i + 1..n {
+            let distance = 1.0 - cosine(&vectors[i], &vectors[j]);
+            distances[i][j] = distance;
+            distances[j][i] = distance;
+        }
+    }
+
+    let dendrogram = Dendrogram::new(distances);
+    let target = match speakers {
+        Some(speakers) => speakers.max(1),
+        None => estimate_speakers(&dendrogram.distances()),
+    };
+    dendrogram.cut(target)
+}
+
+/// Merge history of average-linkage clustering, down to a single cluster
+struct Dendrogram {
+    len: usize,
+    /// Each merge joins the clusters holding these two vectors, at this
+    /// distance, in increasing distance
+    merges: Vec<(usize, usize, f32)>,
+}
+
+impl Dendrogram {
+    /// Nearest-neighbor chain, O(n²)
+    ///
+    /// Follows nearest neighbors until two clusters are each other's nearest,
+    /// and merges them. The merged cluster keeps the row of the first one,
+    /// updated with the Lance–Williams formula for average linkage:
+    /// d(k, a∪b) = (|a|·d(k, a) + |b|·d(k, b)) / (|a| + |b|). Average linkage
+    /// never merges closer than a previous merge, so sorting the merges by
+    /// distance gives the order of the greedy closest-pair algorithm.
+    fn new(mut distances: Vec<Vec<f32>>) -> Self {
+        let len = distances.len();
+        let mut size = vec![1; len];
+        let mut active = vec![true; len];
+        let mut chain = Vec::new();
+        let mut merges = Vec::with_capacity(len.saturating_sub(1));
+
+        while merges.len() + 1 < len {
+            if chain.is_empty() {
+                chain.extend(active.iter().position(|&active| active));
+            }
+            let a = chain[chain.len() - 1];
+            // Prefer the previous cluster of the chain on ties, so the chain ends
+            let previous = chain.len().checked_sub(2).map(|i| chain[i]);
+            let mut nearest = previous.map(|p| (p, distances[a][p]));
+            for b in (0..len).filter(|&b| active[b] && b != a) {
+                if nearest.is_none_or(|(_, distance)| distances[a][b] < distance) {
+                    nearest = Some((b, distances[a][b]));
+                }
+            }
+            let (b, distance) = nearest.expect("at least two clusters are left");
+
+            if Some(b) != previous {
+                chain.push(b);
+                continue;
+            }
+            chain.truncate(chain.len() - 2);
+
+            let (size_a, size_b) = (size[a] as f32, size[b] as f32);
+            for k in (0..len).filter(|&k| active[k] && k != a && k != b) {
+                let merged =
+                    (size_a * distances[a][k] + size_b * distances[b][k]) / (size_a + size_b);
+                distances[a][k] = merged;
+                distances[k][a] = merged;
+            }
+            active[b] = false;
+            size[a] += size[b];
+            merges.push((a, b, distance));
+        }
+
+        merges.sort_by(|x, y| x.2.total_cmp(&y.2));
+        Self { len, merges }
+    }
+
+    /// Distance of every merge, in order
+    fn distances(&self) -> Vec<f32> {
+        self.merges
+            .iter()
+            .map(|&(_, _, distance)| distance)
+            .collect()
+    }
+
+    /// Cluster id per vector, from 0 in order of first appearance, once all
+    /// but the last `clusters - 1` merges are done
+    fn cut(&self, clusters: usize) -> Vec<usize> {
+        fn root(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+
+        let mut parent = (0..self.len).collect::<Vec<_>>();
+        let done = self.len.saturating_sub(clusters.max(1));
+        for &(a, b, _) in &self.merges[..done] {
+            let (a, b) = (root(&mut parent, a), root(&mut parent, b));
+            parent[b] = a;
+        }
+
+        let mut roots = Vec::new();
+        (0..self.len)
+            .map(|i| {
+                let root = root(&mut parent, i);
+                roots.iter().position(|&r| r == root).unwrap_or_else(|| {
+                    roots.push(root);
+                    roots.len() - 1
+                })
+            })
+            .collect()
+    }
+}
+
+/// Number of speakers from the distances of a full agglomeration
+///
+/// Merges within a speaker sit at the spread of its voice, whatever the scale
+/// of the embeddings, and joining two speakers jumps above it. The count `k`
+/// whose first merge between speakers is the furthest above the median of the
+/// merges before it wins, if that's [`SEPARATION`] times above; otherwise
+/// everyone is one speaker. Each speaker needs two segments on average, so
+/// short recordings fall back to one speaker.
+fn estimate_speakers(merges: &[f32]) -> usize {
+    let n = merges.len() + 1;
+    (2..=n / 2)
+        .map(|k| {
+            let (within, between) = merges.split_at(n - k);
+            let mut within = within.to_vec();
+            within.sort_by(f32::total_cmp);
+            let median = within[within.len() / 2];
+            (k, between[0] / median.max(f32::EPSILON))
+        })
+        .filter(|&(_, jump)| jump > SEPARATION)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(1, |(k, _)| k)
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot = a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms > 0.0 { dot / norms } else { 0.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `count` noisy copies of `voice`, from a fixed seed
+    fn segments(voice: &[f32], count: usize, seed: &mut u32) -> Vec<Vec<f32>> {
+        (0..count)
+            .map(|_| {
+                voice
+                    .iter()
+                    .map(|x| {
+                        *seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                        x + (*seed >> 8) as f32 / (1 << 24) as f32 - 0.5
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn voice(offset: f32) -> Vec<f32> {
+        (0..2 * COEFFICIENTS)
+            .map(|d| offset + (d as f32).sin() * 4.0)
+            .collect()
+    }
+
+    #[test]
+    fn one_speaker() {
+        for mut seed in 0..10 {
+            let vectors = standardize(segments(&voice(0.0), 20, &mut seed));
+            let clusters = cluster(&vectors, None);
+            assert!(clusters.iter().all(|&c| c == clusters[0]), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn two_speakers() {
+        for mut seed in 0..10 {
+            let mut vectors = segments(&voice(0.0), 10, &mut seed);
+            vectors.extend(segments(&voice(3.0), 10, &mut seed));
+            let clusters = cluster(&standardize(vectors), None);
+
+            let (first, second) = clusters.split_at(10);
+            assert!(first.iter().all(|&c| c == first[0]), "seed {seed}");
+            assert!(second.iter().all(|&c| c == second[0]), "seed {seed}");
+            assert_ne!(first[0], second[0], "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn given_speaker_count() {
+        let mut seed = 7;
+        let vectors = standardize(segments(&voice(0.0), 12, &mut seed));
+        let clusters = cluster(&vectors, Some(3));
+        let mut ids = clusters.clone();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids.len(), 3);
+    }
+
+    #[test]
+    fn dendrogram_cuts() {
+        // Points at 0, 1, 5 and 6 on a line
+        let points = [0.0_f32, 1.0, 5.0, 6.0];
+        let distances = points
+            .iter()
+            .map(|a| points.iter().map(|b| (a - b).abs()).collect())
+            .collect();
+        let dendrogram = Dendrogram::new(distances);
+
+        // The last merge averages the four distances between the pairs
+        assert_eq!(dendrogram.distances(), [1.0, 1.0, 5.0]);
+        assert_eq!(dendrogram.cut(1), [0, 0, 0, 0]);
+        assert_eq!(dendrogram.cut(2), [0, 0, 1, 1]);
+        assert_eq!(dendrogram.cut(4), [0, 1, 2, 3]);
+        assert_eq!(dendrogram.cut(9), [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn too_few_segments() {
+        assert_eq!(estimate_speakers(&[]), 1);
+        assert_eq!(estimate_speakers(&[0.1, 2.0]), 1);
+        assert_eq!(estimate_speakers(&[0.1, 0.1, 2.0]), 2);
+    }
+}
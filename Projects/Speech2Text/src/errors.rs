@@ -1,7 +1,13 @@
+use std::path::PathBuf;
+
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum Error {
+    // Cli
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+
     // Config
     #[error("Didn't find any GGLM model in `ggml`")]
     GgmlNotFound,
@@ -15,22 +21,123 @@ pub enum Error {
     CodecTypeNull,
     #[error("Audio: UnsupportedSampleFormat")]
     UnsupportedSampleFormat,
-    #[error("Audio: Unsupport {0}")]
-    Unsupoort(String),
+    #[error("Audio: unsupported format `{0}`")]
+    Unsupported(String),
     #[error("ResamplerContraction: {0:?}")]
     ResamplerContruction(#[from] rubato::ResamplerConstructionError),
     #[error("Resample: {0:?}")]
     Resample(#[from] rubato::ResampleError),
 
+    // Context
+    #[error("{}: {source}", path.display())]
+    Audio { path: PathBuf, source: Box<Error> },
+    #[error("Model {}: {source}", path.display())]
+    Model { path: PathBuf, source: Box<Error> },
+    #[error("Model failed to load: {0}")]
+    ModelLoad(String),
+    #[error("{} with {}: {source}", audio.display(), model.display())]
+    Transcribe {
+        audio: PathBuf,
+        model: PathBuf,
+        source: Box<Error>,
+    },
+
     // Io
     #[error("Io: {0:?}")]
     Io(#[from] std::io::Error),
 
+    // Json
+    #[error("Json: {0:?}")]
+    Json(#[from] serde_json::Error),
+
+    // Task
+    #[error("Task: {0:?}")]
+    Join(#[from] tokio::task::JoinError),
+
+    // Server
+    #[error("Server: invalid address {0}")]
+    InvalidAddress(#[from] std::net::AddrParseError),
+
     // Whisper (crate)
     #[error("Whisper: {0:?}")]
     Whisper(#[from] whisper_rs::WhisperError),
 
+    // Hound (crate)
+    #[error("Hound: {0:?}")]
+    Hound(#[from] hound::Error),
+
+    // Notify (crate)
+    #[error("Notify: {0:?}")]
+    Notify(#[from] notify::Error),
+
     // Symphonia (crate)
     #[error("Symphonia: {0:?}")]
     Symphonia(#[from] symphonia::core::errors::Error),
 }
+
+impl Error {
+    /// Attach the audio file the error happened on
+    pub fn audio(path: impl Into<PathBuf>) -> impl FnOnce(Error) -> Error {
+        let path = path.into();
+        move |source| Error::Audio {
+            path,
+            source: Box::new(source),
+        }
+    }
+
+    /// Attach the model file the error happened on
+    pub fn model(path: impl Into<PathBuf>) -> impl FnOnce(Error) -> Error {
+        let path = path.into();
+        move |source| Error::Model {
+            path,
+            source: Box::new(source),
+        }
+    }
+
+    /// Attach the audio and model files a transcription failed on
+    pub fn transcribe(
+        audio: impl Into<PathBuf>,
+        model: impl Into<PathBuf>,
+    ) -> impl FnOnce(Error) -> Error {
+        let (audio, model) = (audio.into(), model.into());
+        move |source| Error::Transcribe {
+            audio,
+            model,
+            source: Box::new(source),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn context_names_the_files() {
+        let audio = Error::audio("audio/a.mp3")(Error::Unsupported("flac".to_string()));
+        assert_eq!(
+            audio.to_string(),
+            "audio/a.mp3: Audio: unsupported format `flac`"
+        );
+
+        let model = Error::model("ggml/base.bin")(Error::InvalidAudioFormat);
+        assert_eq!(model.to_string(), "Model ggml/base.bin: InvalidAudioFormat");
+
+        let transcribe =
+            Error::transcribe("audio/a.mp3", "ggml/base.bin")(Error::ModelLoad("Io".to_string()));
+        assert_eq!(
+            transcribe.to_string(),
+            "audio/a.mp3 with ggml/base.bin: Model failed to load: Io"
+        );
+    }
+
+    #[test]
+    fn arguments() {
+        let error = Error::InvalidArgument("--quality ultra".to_string());
+        assert_eq!(error.to_string(), "Invalid argument: --quality ultra");
+        assert_eq!(
+            Error::GgmlNotFound.to_string(),
+            "Didn't find any GGLM model in `ggml`"
+        );
+    }
+}
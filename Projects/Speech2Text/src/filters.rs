@@ -0,0 +1,243 @@
+use std::f32::consts::PI;
+
+/// Sample rate the filters run at
+const SAMPLE_RATE: f32 = 16000.0;
+
+/// Preprocessing applied to the 16 kHz samples before they reach whisper
+///
+/// Runs high-pass, noise gate, then normalization, so the normalization gain is computed on the
+/// cleaned signal
+#[derive(Debug, Clone, Default)]
+pub struct Filters {
+    pub high_pass: Option<HighPass>,
+    pub noise_gate: Option<NoiseGate>,
+    pub normalize: Option<Normalize>,
+}
+
+impl Filters {
+    /// Stable encoding of the filters and their settings, part of the transcript cache key
+    pub fn cache_key(&self) -> String {
+        // Destructured so a new filter can't be left out
+        let Self {
+            high_pass,
+            noise_gate,
+            normalize,
+        } = self;
+        let mut key = String::new();
+        if let Some(HighPass { cutoff }) = high_pass {
+            key.push_str(&format!("highpass={cutoff};"));
+        }
+        if let Some(NoiseGate { threshold }) = noise_gate {
+            key.push_str(&format!("gate={threshold};"));
+        }
+        match normalize {
+            Some(Normalize::Peak(target)) => key.push_str(&format!("normalize=peak:{target};")),
+            Some(Normalize::Loudness(target)) => {
+                key.push_str(&format!("normalize=loudness:{target};"))
+            }
+            None => {}
+        }
+        key
+    }
+
+    pub fn apply(&self, samples: &mut [f32]) {
+        if let Some(high_pass) = &self.high_pass {
+            high_pass.apply(samples);
+        }
+        if let Some(noise_gate) = &self.noise_gate {
+            noise_gate.apply(samples);
+        }
+        if let Some(normalize) = &self.normalize {
+            normalize.apply(samples);
+        }
+    }
+}
+
+/// Second-order Butterworth high-pass, removes rumble below `cutoff`
+#[derive(Debug, Clone)]
+pub struct HighPass {
+    /// Cutoff frequency in Hz
+    pub cutoff: f32,
+}
+
+impl HighPass {
+    /// Nyquist frequency, the cutoff must stay below it for the filter to be stable
+    pub const MAX_CUTOFF: f32 = SAMPLE_RATE / 2.0;
+
+    fn apply(&self, samples: &mut [f32]) {
+        // RBJ audio EQ cookbook coefficients, Q = 1/sqrt(2)
+        let w0 = 2.0 * PI * self.cutoff / SAMPLE_RATE;
+        let alpha = w0.sin() / (2.0 * std::f32::consts::FRAC_1_SQRT_2);
+        let cos = w0.cos();
+
+        let a0 = 1.0 + alpha;
+        let b0 = (1.0 + cos) / 2.0 / a0;
+        let b1 = -(1.0 + cos) / a0;
+        let b2 = b0;
+        let a1 = -2.0 * cos / a0;
+        let a2 = (1.0 - alpha) / a0;
+
+        let (mut x1, mut x2, mut y1, mut y2) = (0.0, 0.0, 0.0, 0.0);
+        for sample in samples {
+            let x0 = *sample;
+            let y0 = b0 * x0 + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2;
+            (x2, x1) = (x1, x0);
+            (y2, y1) = (y1, y0);
+            *sample = y0;
+        }
+    }
+}
+
+/// Silences the signal while its level stays below `threshold`
+#[derive(Debug, Clone)]
+pub struct NoiseGate {
+    /// Threshold in dBFS
+    pub threshold: f32,
+}
+
+impl NoiseGate {
+    /// Time for the gate to open
+    const ATTACK: f32 = 0.005;
+    /// Time for the gate to close, long enough not to chop word endings
+    const RELEASE: f32 = 0.150;
+
+    fn apply(&self, samples: &mut [f32]) {
+        let threshold = db_to_amplitude(self.threshold);
+        let attack = (-1.0 / (Self::ATTACK * SAMPLE_RATE)).exp();
+        let release = (-1.0 / (Self::RELEASE * SAMPLE_RATE)).exp();
+
+        let mut envelope = 0.0_f32;
+        let mut gain = 0.0_f32;
+        for sample in samples {
+            // Peak envelope follower
+            let level = sample.abs();
+            let coefficient = if level > envelope { attack } else { release };
+            envelope = coefficient * envelope + (1.0 - coefficient) * level;
+
+            // Smooth the gain too, so opening and closing doesn't click
+            let target = if envelope >= threshold { 1.0 } else { 0.0 };
+            let coefficient = if target > gain { attack } else { release };
+            gain = coefficient * gain + (1.0 - coefficient) * target;
+
+            *sample *= gain;
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Normalize {
+    /// Scale so the loudest sample reaches the target, in dBFS
+    Peak(f32),
+    /// Scale so the RMS level reaches the target, in dBFS, without clipping
+    Loudness(f32),
+}
+
+impl Normalize {
+    pub const PEAK_TARGET: f32 = -1.0;
+    pub const LOUDNESS_TARGET: f32 = -20.0;
+
+    fn apply(&self, samples: &mut [f32]) {
+        let peak = samples.iter().fold(0.0_f32, |peak, s| peak.max(s.abs()));
+        if peak == 0.0 {
+            return;
+        }
+
+        let gain = match self {
+            Normalize::Peak(target) => db_to_amplitude(*target) / peak,
+            Normalize::Loudness(target) => {
+                let rms =
+                    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
+                (db_to_amplitude(*target) / rms).min(1.0 / peak)
+            }
+        };
+
+        for sample in samples {
+            *sample *= gain;
+        }
+    }
+}
+
+fn db_to_amplitude(db: f32) -> f32 {
+    10.0_f32.powf(db / 20.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One second of a sine at `frequency` Hz
+    fn tone(frequency: f32, amplitude: f32) -> Vec<f32> {
+        (0..SAMPLE_RATE as usize)
+            .map(|i| amplitude * (2.0 * PI * frequency * i as f32 / SAMPLE_RATE).sin())
+            .collect()
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    fn peak(samples: &[f32]) -> f32 {
+        samples.iter().fold(0.0, |peak, s| peak.max(s.abs()))
+    }
+
+    #[test]
+    fn high_pass_removes_rumble() {
+        let high_pass = HighPass { cutoff: 80.0 };
+        // Skip the filter's settling time
+        let tail = SAMPLE_RATE as usize / 2..;
+
+        let mut dc = vec![0.5; SAMPLE_RATE as usize];
+        high_pass.apply(&mut dc);
+        assert!(peak(&dc[tail.clone()]) < 0.001);
+
+        let mut low = tone(20.0, 0.5);
+        let before = rms(&low[tail.clone()]);
+        high_pass.apply(&mut low);
+        assert!(rms(&low[tail.clone()]) < before * 0.1);
+
+        let mut voice = tone(1000.0, 0.5);
+        let before = rms(&voice[tail.clone()]);
+        high_pass.apply(&mut voice);
+        assert!(rms(&voice[tail]) > before * 0.95);
+    }
+
+    #[test]
+    fn gate_mutes_quiet_noise() {
+        let gate = NoiseGate { threshold: -45.0 };
+
+        // -60 dBFS hiss never opens the gate
+        let mut noise = tone(3000.0, db_to_amplitude(-60.0));
+        gate.apply(&mut noise);
+        assert_eq!(peak(&noise), 0.0);
+
+        // Speech level passes once the gate is open
+        let mut voice = tone(500.0, db_to_amplitude(-20.0));
+        let before = rms(&voice[1000..]);
+        gate.apply(&mut voice);
+        assert!(rms(&voice[1000..]) > before * 0.95);
+    }
+
+    #[test]
+    fn peak_normalization_reaches_its_target() {
+        let mut samples = tone(440.0, 0.1);
+        Normalize::Peak(Normalize::PEAK_TARGET).apply(&mut samples);
+        assert!((peak(&samples) - db_to_amplitude(Normalize::PEAK_TARGET)).abs() < 1e-4);
+
+        let mut silence = vec![0.0; 100];
+        Normalize::Peak(Normalize::PEAK_TARGET).apply(&mut silence);
+        assert_eq!(peak(&silence), 0.0);
+    }
+
+    #[test]
+    fn loudness_normalization_reaches_its_target_without_clipping() {
+        let mut samples = tone(440.0, 0.01);
+        Normalize::Loudness(Normalize::LOUDNESS_TARGET).apply(&mut samples);
+        assert!((rms(&samples) - db_to_amplitude(Normalize::LOUDNESS_TARGET)).abs() < 1e-4);
+
+        // A single click in silence would need a huge gain, it stops at full scale
+        let mut click = vec![0.0; 1000];
+        click[500] = 0.1;
+        Normalize::Loudness(Normalize::LOUDNESS_TARGET).apply(&mut click);
+        assert!((peak(&click) - 1.0).abs() < 1e-4);
+    }
+}
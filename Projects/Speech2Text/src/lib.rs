@@ -1,12 +1,27 @@
 mod audio;
+mod benchmark;
+mod cache;
+mod cli;
 mod config;
+mod diarize;
 mod errors;
+mod filters;
+mod server;
 mod speech2text;
+mod summary;
 pub mod trace;
+mod transcript;
+mod watch;
 mod whisper;
 
+use cache::Cache;
+use cli::Options;
+pub use cli::{Args, Command};
 use config::Config;
 pub use speech2text::Speech2Text;
+use summary::Status;
+pub use summary::Summary;
+use transcript::Segment;
 
 pub use errors::Error;
 pub type Result<T> = std::result::Result<T, Error>;
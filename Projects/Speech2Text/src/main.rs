@@ -1,11 +1,28 @@
+use std::process::ExitCode;
+
 use speech2text::*;
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> Result<ExitCode> {
     trace::setup_tracing();
 
-    let speech2text = Speech2Text::new().await?;
-    speech2text.run().await?;
+    let args = Args::parse()?;
+
+    let speech2text = Speech2Text::new(args.options).await?;
+    match args.command {
+        Command::Run => {
+            let summary = speech2text.run().await?;
+            println!("{summary}");
+
+            // Let CI pipelines notice failed files
+            if summary.has_failures() {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::Bench => speech2text.benchmark().await?,
+        Command::Watch => speech2text.watch().await?,
+        Command::Serve(addr) => speech2text.serve(addr).await?,
+    }
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
@@ -0,0 +1,337 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::Router;
+use axum::extract::{DefaultBodyLimit, Multipart, Path as UrlPath, Query, State};
+use axum::http::{StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot};
+use uuid::Uuid;
+use whisper_rs::WhisperContext;
+
+use super::*;
+use transcript::Format;
+
+/// Largest accepted upload
+const MAX_UPLOAD: usize = 512 * 1024 * 1024;
+
+/// How long a finished job stays available at `GET /jobs/{id}`
+const JOB_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Most finished jobs kept at once, the oldest are evicted first
+const MAX_FINISHED_JOBS: usize = 1000;
+
+#[derive(Clone)]
+struct AppState {
+    models: Arc<Vec<PathBuf>>,
+    jobs: Arc<Mutex<Jobs>>,
+    queue: mpsc::UnboundedSender<Job>,
+}
+
+/// A queued transcription, handled by the single worker thread
+struct Job {
+    id: Uuid,
+    model: PathBuf,
+    audio: Vec<u8>,
+    extension: String,
+    done: Option<oneshot::Sender<()>>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+enum JobStatus {
+    Queued,
+    Running,
+    Done { segments: Vec<Segment> },
+    Failed { error: String },
+}
+
+/// Status of every job, finished ones evicted after `JOB_TTL` or past `MAX_FINISHED_JOBS`
+#[derive(Default)]
+struct Jobs {
+    /// Status and, once finished, when it finished
+    statuses: HashMap<Uuid, (JobStatus, Option<Instant>)>,
+}
+
+impl Jobs {
+    fn set(&mut self, id: Uuid, status: JobStatus) {
+        let now = Instant::now();
+        let finished = matches!(status, JobStatus::Done { .. } | JobStatus::Failed { .. });
+        self.statuses.insert(id, (status, finished.then_some(now)));
+        self.prune(now);
+    }
+
+    fn get(&self, id: &Uuid) -> Option<&JobStatus> {
+        self.statuses.get(id).map(|(status, _)| status)
+    }
+
+    /// Evict the expired jobs, then the oldest finished ones beyond the cap
+    fn prune(&mut self, now: Instant) {
+        self.statuses.retain(|_, (_, finished)| {
+            finished.is_none_or(|finished| now.duration_since(finished) < JOB_TTL)
+        });
+
+        let mut finished = self
+            .statuses
+            .iter()
+            .filter_map(|(id, (_, finished))| Some(((*finished)?, *id)))
+            .collect::<Vec<_>>();
+        if finished.len() > MAX_FINISHED_JOBS {
+            finished.sort_unstable();
+            for (_, id) in &finished[..finished.len() - MAX_FINISHED_JOBS] {
+                self.statuses.remove(id);
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TranscribeQuery {
+    #[serde(default)]
+    format: Format,
+    /// Model file name in `ggml`, defaults to the first one found
+    model: Option<String>,
+    /// Wait for the result instead of returning the job id right away
+    #[serde(default = "default_wait")]
+    wait: bool,
+}
+
+fn default_wait() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+struct JobQuery {
+    #[serde(default)]
+    format: Format,
+}
+
+pub async fn run(config: Config, addr: SocketAddr) -> Result<()> {
+    let (queue, rx) = mpsc::unbounded_channel();
+    let jobs = Arc::new(Mutex::new(Jobs::default()));
+
+    // Whisper is blocking, so the worker gets its own thread
+    let worker_jobs = jobs.clone();
+    let options = config.options.clone();
+    std::thread::spawn(move || worker(rx, worker_jobs, options));
+
+    let state = AppState {
+        models: Arc::new(config.gglm),
+        jobs,
+        queue,
+    };
+
+    let app = Router::new()
+        .route("/transcribe", post(transcribe))
+        .route("/jobs/{id}", get(job))
+        .layer(DefaultBodyLimit::max(MAX_UPLOAD))
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    tracing::info!("Listening on {}", listener.local_addr()?);
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
+
+/// Process jobs one at a time, loading each model at most once
+fn worker(mut rx: mpsc::UnboundedReceiver<Job>, jobs: Arc<Mutex<Jobs>>, options: Options) {
+    let mut models: HashMap<PathBuf, WhisperContext> = HashMap::new();
+
+    while let Some(job) = rx.blocking_recv() {
+        set_status(&jobs, job.id, JobStatus::Running);
+        tracing::info!("Job {}: start", job.id);
+
+        let result =
+            audio::from_bytes(job.audio, &job.extension, &options.audio).and_then(|samples| {
+                let model = match models.entry(job.model.clone()) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => {
+                        tracing::info!("Loading model {}", job.model.display());
+                        entry.insert(
+                            whisper::create_model(&job.model).map_err(Error::model(&job.model))?,
+                        )
+                    }
+                };
+                let mut segments = whisper::run(model, &samples)?;
+                if let Some(diarization) = &options.diarization {
+                    diarization.apply(&samples, &mut segments);
+                }
+                Ok(segments)
+            });
+
+        let status = match result {
+            Ok(segments) => JobStatus::Done { segments },
+            Err(e) => {
+                tracing::error!("Job {}: {e}", job.id);
+                JobStatus::Failed {
+                    error: e.to_string(),
+                }
+            }
+        };
+        set_status(&jobs, job.id, status);
+        tracing::info!("Job {}: finished", job.id);
+
+        if let Some(done) = job.done {
+            let _ = done.send(());
+        }
+    }
+}
+
+fn set_status(jobs: &Mutex<Jobs>, id: Uuid, status: JobStatus) {
+    jobs.lock().unwrap().set(id, status);
+}
+
+/// `POST /transcribe`: multipart upload with a `file` field
+async fn transcribe(
+    State(state): State<AppState>,
+    Query(query): Query<TranscribeQuery>,
+    mut multipart: Multipart,
+) -> Response {
+    let model = match &query.model {
+        Some(name) => state
+            .models
+            .iter()
+            .find(|path| path.file_name().and_then(|f| f.to_str()) == Some(name.as_str())),
+        None => state.models.first(),
+    };
+    let Some(model) = model.cloned() else {
+        return error(StatusCode::BAD_REQUEST, "Unknown model");
+    };
+
+    // Find the uploaded file
+    let mut upload = None;
+    loop {
+        match multipart.next_field().await {
+            Ok(Some(field)) if field.name() == Some("file") => {
+                let extension = field
+                    .file_name()
+                    .and_then(|name| Path::new(name).extension())
+                    .and_then(|ext| ext.to_str())
+                    .unwrap_or_default()
+                    .to_lowercase();
+                match field.bytes().await {
+                    Ok(bytes) => upload = Some((bytes.to_vec(), extension)),
+                    Err(e) => return error(StatusCode::BAD_REQUEST, &e.to_string()),
+                }
+                break;
+            }
+            Ok(Some(_)) => continue,
+            Ok(None) => break,
+            Err(e) => return error(StatusCode::BAD_REQUEST, &e.to_string()),
+        }
+    }
+    let Some((audio, extension)) = upload else {
+        return error(StatusCode::BAD_REQUEST, "Missing `file` field");
+    };
+
+    let id = Uuid::new_v4();
+    set_status(&state.jobs, id, JobStatus::Queued);
+
+    let (done, finished) = oneshot::channel();
+    let job = Job {
+        id,
+        model,
+        audio,
+        extension,
+        done: query.wait.then_some(done),
+    };
+    if state.queue.send(job).is_err() {
+        return error(StatusCode::SERVICE_UNAVAILABLE, "Worker stopped");
+    }
+
+    if !query.wait {
+        return (
+            StatusCode::ACCEPTED,
+            [(header::LOCATION, format!("/jobs/{id}"))],
+            axum::Json(serde_json::json!({ "id": id, "status": "queued" })),
+        )
+            .into_response();
+    }
+
+    let _ = finished.await;
+    respond(&state, id, query.format)
+}
+
+/// `GET /jobs/{id}`: job status, or the transcript once done
+async fn job(
+    State(state): State<AppState>,
+    UrlPath(id): UrlPath<Uuid>,
+    Query(query): Query<JobQuery>,
+) -> Response {
+    respond(&state, id, query.format)
+}
+
+fn respond(state: &AppState, id: Uuid, format: Format) -> Response {
+    let Some(status) = state.jobs.lock().unwrap().get(&id).cloned() else {
+        return error(StatusCode::NOT_FOUND, "Unknown job");
+    };
+
+    match status {
+        JobStatus::Done { segments } if format != Format::Json => (
+            [(header::CONTENT_TYPE, format.content_type())],
+            format.render(&segments),
+        )
+            .into_response(),
+        JobStatus::Done { .. } => job_json(StatusCode::OK, id, &status),
+        JobStatus::Failed { .. } => job_json(StatusCode::UNPROCESSABLE_ENTITY, id, &status),
+        JobStatus::Queued | JobStatus::Running => job_json(StatusCode::ACCEPTED, id, &status),
+    }
+}
+
+fn job_json(code: StatusCode, id: Uuid, status: &JobStatus) -> Response {
+    let mut body = serde_json::to_value(status).unwrap_or_default();
+    body["id"] = serde_json::json!(id);
+    (code, axum::Json(body)).into_response()
+}
+
+fn error(code: StatusCode, message: &str) -> Response {
+    (code, axum::Json(serde_json::json!({ "error": message }))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failed() -> JobStatus {
+        JobStatus::Failed {
+            error: "Invalid audio".to_string(),
+        }
+    }
+
+    #[test]
+    fn finished_jobs_expire() {
+        let mut jobs = Jobs::default();
+        let (running, finished) = (Uuid::new_v4(), Uuid::new_v4());
+        jobs.set(running, JobStatus::Running);
+        jobs.set(finished, failed());
+
+        jobs.prune(Instant::now() + JOB_TTL / 2);
+        assert!(jobs.get(&finished).is_some());
+
+        jobs.prune(Instant::now() + JOB_TTL);
+        assert!(jobs.get(&finished).is_none());
+        assert!(jobs.get(&running).is_some());
+    }
+
+    #[test]
+    fn finished_jobs_are_capped() {
+        let mut jobs = Jobs::default();
+        let first = Uuid::new_v4();
+        jobs.set(first, failed());
+        std::thread::sleep(Duration::from_millis(1));
+        for _ in 0..MAX_FINISHED_JOBS {
+            jobs.set(Uuid::new_v4(), failed());
+        }
+        jobs.set(Uuid::new_v4(), JobStatus::Queued);
+
+        assert_eq!(jobs.statuses.len(), MAX_FINISHED_JOBS + 1);
+        assert!(jobs.get(&first).is_none());
+    }
+}
@@ -1,3 +1,4 @@
+use std::net::SocketAddr;
 use std::time::Instant;
 
 use super::*;
@@ -7,34 +8,48 @@ pub struct Speech2Text {
 }
 
 impl Speech2Text {
-    pub async fn new() -> Result<Self> {
-        let config = Config::new().await?;
+    pub async fn new(options: Options) -> Result<Self> {
+        let config = Config::new(options).await?;
 
         whisper::init();
 
         Ok(Self { config })
     }
 
-    pub async fn run(self) -> Result<()> {
+    /// Transcribe every audio file with every model
+    ///
+    /// Failures don't stop the batch; they're collected in the returned [`Summary`]
+    pub async fn run(self) -> Result<Summary> {
+        if self.config.audio.is_empty() {
+            return Err(Error::AudioNotFound);
+        }
+
         // Initialize models
         tracing::info!("Initialing models");
 
-        // Create model
+        // Create model, a model failing to load fails its items and leaves the others running
         let mut models = Vec::new();
         for gglm in self.config.gglm {
-            let model = whisper::create_model(&gglm)?;
+            let loaded = whisper::create_model(&gglm)
+                .and_then(|model| Ok((model, cache::hash_file(&gglm)?)))
+                .map_err(|e| {
+                    let reason = e.to_string();
+                    tracing::error!("{}", Error::model(&gglm)(e));
+                    reason
+                });
             let file_name = gglm
                 .file_name()
                 .and_then(|f| f.to_str())
                 .unwrap_or("Invalid GGML")
                 .to_string();
-            models.push((model, file_name))
+            models.push((loaded, gglm, file_name))
         }
 
-        for audio in &self.config.audio {
-            //let sample = audio::run(&audio);
-            let audio = audio.to_path_buf();
+        let cache = Cache::new();
+        let options = &self.config.options;
+        let mut summary = Summary::default();
 
+        for audio in &self.config.audio {
             // Try to extract file name from path
             // If None, fall back to full path
             // Default to `InvalidAudio` if neither is valid
@@ -43,40 +58,125 @@ impl Speech2Text {
                 .and_then(|f| f.to_str())
                 .unwrap_or_else(|| audio.to_str().unwrap_or("InvalidAudio"));
 
-            let samples = match audio::run(&audio) {
-                Ok(sample) => sample,
+            let audio_hash = match cache::hash_file(audio).map_err(Error::audio(audio)) {
+                Ok(hash) => hash,
                 Err(e) => {
-                    tracing::error!("Audio {audio_name}: {e}");
+                    tracing::error!("{e}");
+                    summary.push(audio_name, None, Status::Failed(e));
                     continue;
                 }
             };
+            // Decoded once the first model misses the cache, or for diarization
+            let mut samples = None;
 
             // Process audio with each ggml model
-            for (model, ggml_name) in &models {
+            for (loaded, ggml, ggml_name) in &models {
+                let (model, model_hash) = match loaded {
+                    Ok(loaded) => loaded,
+                    Err(reason) => {
+                        let e = Error::transcribe(audio, ggml)(Error::ModelLoad(reason.clone()));
+                        summary.push(audio_name, Some(ggml_name), Status::Failed(e));
+                        continue;
+                    }
+                };
                 let start = Instant::now();
                 tracing::info!("Start processing {} with {}", audio_name, ggml_name);
 
-                match whisper::run(model, &samples) {
-                    Ok(transcribe) => {
+                let key = Cache::key(&audio_hash, model_hash, &options.audio);
+                let cached = if options.force {
+                    None
+                } else {
+                    cache.load(&key)
+                };
+
+                if samples.is_none() && (cached.is_none() || options.diarization.is_some()) {
+                    match audio::run(audio, &options.audio).map_err(Error::audio(audio)) {
+                        Ok(decoded) => samples = Some(decoded),
+                        Err(e) => {
+                            tracing::error!("{e}");
+                            summary.push(audio_name, None, Status::Failed(e));
+                            break;
+                        }
+                    }
+                }
+                let samples = samples.as_deref().unwrap_or_default();
+
+                let (result, status) = match cached {
+                    Some(transcribe) => {
+                        tracing::info!("Loaded {} with {} from cache", audio_name, ggml_name);
+                        (Ok(transcribe), Status::Skipped("cached".to_string()))
+                    }
+                    None => {
+                        let result = whisper::run(model, samples).inspect(|transcribe| {
+                            if let Err(e) = cache.store(&key, transcribe) {
+                                tracing::warn!("Cache: {e}");
+                            }
+                        });
+                        (result, Status::Succeeded)
+                    }
+                };
+
+                match result {
+                    Ok(mut transcribe) => {
+                        if let Some(diarization) = &options.diarization {
+                            diarization.apply(samples, &mut transcribe);
+                        }
+
                         let duration = start.elapsed();
                         tracing::info!("Time usage: {}ms", duration.as_millis());
 
-                        for (start, end, text) in transcribe {
-                            tracing::info!("[{:5} - {:5}ms] {}", start, end, text);
+                        for segment in transcribe {
+                            tracing::info!(
+                                "[{:5} - {:5}ms] {}",
+                                segment.start,
+                                segment.end,
+                                segment.labeled_text()
+                            );
                         }
+
+                        summary.push(audio_name, Some(ggml_name), status);
                     }
                     Err(e) => {
-                        tracing::error!(
-                            "Error while process {} with {}: {}",
-                            audio_name,
-                            ggml_name,
-                            e
-                        )
+                        let e = Error::transcribe(audio, ggml)(e);
+                        tracing::error!("{e}");
+                        summary.push(audio_name, Some(ggml_name), Status::Failed(e));
                     }
                 }
             }
         }
 
+        Ok(summary)
+    }
+
+    /// Benchmark every model against every audio file
+    ///
+    /// Prints a table and writes the same numbers to `benchmark.csv`
+    pub async fn benchmark(self) -> Result<()> {
+        if self.config.audio.is_empty() {
+            return Err(Error::AudioNotFound);
+        }
+
+        let records = benchmark::run(&self.config)?;
+
+        println!("{}", benchmark::table(&records));
+
+        let csv_path = benchmark::csv_path();
+        tokio::fs::write(&csv_path, benchmark::csv(&records)).await?;
+        tracing::info!("Benchmark report written to {}", csv_path.display());
+
         Ok(())
     }
+
+    /// Serve transcriptions over HTTP
+    ///
+    /// - `POST /transcribe` with a multipart `file` field
+    /// - `GET /jobs/{id}` to poll a job started with `wait=false`
+    pub async fn serve(self, addr: SocketAddr) -> Result<()> {
+        server::run(self.config, addr).await
+    }
+
+    /// Watch the audio directory and transcribe new or modified files
+    pub async fn watch(self) -> Result<()> {
+        tokio::task::spawn_blocking(move || watch::run(self.config)).await?
+    }
 }
@@ -0,0 +1,133 @@
+use std::fmt;
+
+use super::*;
+
+pub enum Status {
+    Succeeded,
+    /// Transcription wasn't needed, e.g. loaded from cache
+    Skipped(String),
+    Failed(Error),
+}
+
+/// Result of one audio file with one model
+///
+/// `model` is `None` when the file failed before reaching any model
+pub struct Outcome {
+    pub audio: String,
+    pub model: Option<String>,
+    pub status: Status,
+}
+
+/// Per-file outcomes of a batch run
+#[derive(Default)]
+pub struct Summary {
+    pub outcomes: Vec<Outcome>,
+}
+
+impl Summary {
+    pub fn push(&mut self, audio: &str, model: Option<&str>, status: Status) {
+        self.outcomes.push(Outcome {
+            audio: audio.to_string(),
+            model: model.map(str::to_string),
+            status,
+        });
+    }
+
+    pub fn has_failures(&self) -> bool {
+        self.outcomes
+            .iter()
+            .any(|outcome| matches!(outcome.status, Status::Failed(_)))
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let count = |filter: fn(&Status) -> bool| {
+            self.outcomes
+                .iter()
+                .filter(|outcome| filter(&outcome.status))
+                .count()
+        };
+
+        writeln!(
+            f,
+            "Summary: {} succeeded, {} skipped, {} failed",
+            count(|s| matches!(s, Status::Succeeded)),
+            count(|s| matches!(s, Status::Skipped(_))),
+            count(|s| matches!(s, Status::Failed(_))),
+        )?;
+
+        for outcome in &self.outcomes {
+            let subject = match &outcome.model {
+                Some(model) => format!("{} with {}", outcome.audio, model),
+                None => outcome.audio.clone(),
+            };
+            match &outcome.status {
+                Status::Succeeded => writeln!(f, "  ok      {subject}")?,
+                Status::Skipped(reason) => writeln!(f, "  skipped {subject}: {reason}")?,
+                // The error already names the file and model
+                Status::Failed(error) => writeln!(f, "  failed  {error}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure() -> Status {
+        Status::Failed(Error::transcribe("b.wav", "ggml/base.bin")(
+            Error::ModelLoad("Io: missing".to_string()),
+        ))
+    }
+
+    #[test]
+    fn display_lists_every_outcome() {
+        let mut summary = Summary::default();
+        summary.push("a.mp3", Some("base.bin"), Status::Succeeded);
+        summary.push(
+            "a.mp3",
+            Some("small.bin"),
+            Status::Skipped("cached".to_string()),
+        );
+        summary.push("b.wav", Some("base.bin"), failure());
+        summary.push(
+            "c.mp3",
+            None,
+            Status::Failed(Error::audio("c.mp3")(Error::CodecTypeNull)),
+        );
+
+        assert_eq!(
+            summary.to_string(),
+            "Summary: 1 succeeded, 1 skipped, 2 failed\n\
+             \x20 ok      a.mp3 with base.bin\n\
+             \x20 skipped a.mp3 with small.bin: cached\n\
+             \x20 failed  b.wav with ggml/base.bin: Model failed to load: Io: missing\n\
+             \x20 failed  c.mp3: Audio: no supported audio tracks\n"
+        );
+    }
+
+    #[test]
+    fn failures() {
+        let mut summary = Summary::default();
+        assert!(!summary.has_failures());
+        assert_eq!(
+            summary.to_string(),
+            "Summary: 0 succeeded, 0 skipped, 0 failed\n"
+        );
+
+        summary.push("a.mp3", Some("base.bin"), Status::Succeeded);
+        summary.push(
+            "a.mp3",
+            Some("small.bin"),
+            Status::Skipped("cached".to_string()),
+        );
+        assert!(!summary.has_failures());
+
+        summary.push("b.wav", Some("base.bin"), failure());
+        assert!(summary.has_failures());
+    }
+}
@@ -0,0 +1,103 @@
+use std::fmt::Write as _;
+
+use serde::{Deserialize, Serialize};
+
+/// One transcribed segment
+///
+/// `start` and `end` are in whisper's unit of 10ms
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Segment {
+    pub start: i64,
+    pub end: i64,
+    pub text: String,
+    /// `SPEAKER_1`, `SPEAKER_2`, ... when diarization is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>,
+}
+
+impl Segment {
+    /// Text prefixed with the speaker label, if any
+    pub fn labeled_text(&self) -> String {
+        match &self.speaker {
+            Some(speaker) => format!("[{speaker}] {}", self.text.trim()),
+            None => self.text.trim().to_string(),
+        }
+    }
+}
+
+/// Output format of a transcript
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Json,
+    Srt,
+    Vtt,
+}
+
+impl Format {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Format::Json => "application/json",
+            Format::Srt => "application/x-subrip",
+            Format::Vtt => "text/vtt",
+        }
+    }
+
+    pub fn render(&self, segments: &[Segment]) -> String {
+        match self {
+            Format::Json => serde_json::to_string(segments).unwrap_or_default(),
+            Format::Srt => srt(segments),
+            Format::Vtt => vtt(segments),
+        }
+    }
+}
+
+fn srt(segments: &[Segment]) -> String {
+    let mut out = String::new();
+    for (i, segment) in segments.iter().enumerate() {
+        let _ = writeln!(out, "{}", i + 1);
+        let _ = writeln!(
+            out,
+            "{} --> {}",
+            timestamp(segment.start, ','),
+            timestamp(segment.end, ',')
+        );
+        let _ = writeln!(out, "{}\n", segment.labeled_text());
+    }
+    out
+}
+
+fn vtt(segments: &[Segment]) -> String {
+    let mut out = String::from("WEBVTT\n\n");
+    for segment in segments {
+        let _ = writeln!(
+            out,
+            "{} --> {}",
+            timestamp(segment.start, '.'),
+            timestamp(segment.end, '.')
+        );
+        match &segment.speaker {
+            Some(speaker) => {
+                let _ = writeln!(out, "<v {speaker}>{}\n", segment.text.trim());
+            }
+            None => {
+                let _ = writeln!(out, "{}\n", segment.text.trim());
+            }
+        }
+    }
+    out
+}
+
+/// Format a whisper timestamp as `HH:MM:SS<sep>mmm`
+fn timestamp(centiseconds: i64, separator: char) -> String {
+    let ms = centiseconds.max(0) * 10;
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        separator,
+        ms % 1000
+    )
+}
@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use notify::{RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+
+use super::*;
+use transcript::Format;
+
+/// Name of the state file kept in the audio directory
+const STATE_FILE: &str = ".speech2text-state.json";
+
+/// Wait until a file hasn't changed for this long before transcribing it
+const SETTLE: Duration = Duration::from_secs(2);
+
+/// Decode attempts before giving up on a file until it changes again
+const RETRIES: usize = 3;
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+const EXTENSIONS: [&str; 2] = ["mp3", "wav"];
+
+/// Size and modification time of a transcribed file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Fingerprint {
+    size: u64,
+    modified: u128,
+}
+
+impl Fingerprint {
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        let modified = metadata
+            .modified()
+            .ok()?
+            .duration_since(UNIX_EPOCH)
+            .ok()?
+            .as_nanos();
+        Some(Self {
+            size: metadata.len(),
+            modified,
+        })
+    }
+}
+
+/// Files waiting to stop changing, or to be retried, each with when it's due
+#[derive(Default)]
+struct Queue {
+    files: HashMap<PathBuf, Pending>,
+}
+
+struct Pending {
+    due: Instant,
+    /// Decode attempt the file is due for, from 1
+    attempt: usize,
+}
+
+impl Queue {
+    /// Handle the file as soon as possible, like the files present at startup
+    fn ready(&mut self, path: PathBuf, now: Instant) {
+        self.files.insert(
+            path,
+            Pending {
+                due: now,
+                attempt: 1,
+            },
+        );
+    }
+
+    /// The file changed, wait for it to settle, starting its attempts over
+    fn changed(&mut self, path: PathBuf, now: Instant) {
+        self.files.insert(
+            path,
+            Pending {
+                due: now + SETTLE,
+                attempt: 1,
+            },
+        );
+    }
+
+    /// Decode failed, try again after [`RETRY_DELAY`] unless the file changes meanwhile
+    fn retry(&mut self, path: PathBuf, attempt: usize, now: Instant) {
+        self.files.insert(
+            path,
+            Pending {
+                due: now + RETRY_DELAY,
+                attempt,
+            },
+        );
+    }
+
+    /// Removes and returns the files due by `now`, with their attempt
+    fn due(&mut self, now: Instant) -> Vec<(PathBuf, usize)> {
+        let due = self
+            .files
+            .iter()
+            .filter(|(_, pending)| pending.due <= now)
+            .map(|(path, pending)| (path.clone(), pending.attempt))
+            .collect::<Vec<_>>();
+        for (path, _) in &due {
+            self.files.remove(path);
+        }
+        due
+    }
+}
+
+/// Files already transcribed, keyed by file name
+#[derive(Default, Serialize, Deserialize)]
+struct State {
+    files: HashMap<String, Fingerprint>,
+}
+
+impl State {
+    fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|state| serde_json::from_str(&state).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        // Write then rename, so a crash never leaves a truncated state file
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(tmp, path)?;
+        Ok(())
+    }
+}
+
+/// Watch the audio directory and transcribe every new or modified file once
+///
+/// Each transcript is written next to its audio as `<name>.srt`
+pub fn run(config: Config) -> Result<()> {
+    let state_path = config.audio_dir.join(STATE_FILE);
+    let mut state = State::load(&state_path);
+
+    // The daemon uses a single model, the first one in `ggml`
+    let ggml = &config.gglm[0];
+    tracing::info!("Loading model {}", ggml.display());
+    let model = whisper::create_model(ggml).map_err(Error::model(ggml))?;
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(&config.audio_dir, RecursiveMode::NonRecursive)?;
+    tracing::info!("Watching {}", config.audio_dir.display());
+
+    // Files already present are handled like new ones
+    let mut queue = Queue::default();
+    for path in &config.audio {
+        queue.ready(path.clone(), Instant::now());
+    }
+
+    loop {
+        match rx.recv_timeout(Duration::from_millis(500)) {
+            Ok(Ok(event)) if event.kind.is_create() || event.kind.is_modify() => {
+                for path in event.paths {
+                    if is_audio(&path) {
+                        queue.changed(path, Instant::now());
+                    }
+                }
+            }
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => tracing::error!("Watch: {e}"),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+
+        // Handle files that stopped changing, and retries whose delay is over
+        for (path, attempt) in queue.due(Instant::now()) {
+            let Some(name) = path.file_name().and_then(|f| f.to_str()) else {
+                continue;
+            };
+            let Some(fingerprint) = Fingerprint::of(&path) else {
+                // Deleted or renamed away before we got to it
+                continue;
+            };
+            if state.files.get(name) == Some(&fingerprint) {
+                continue;
+            }
+
+            // Decode errors may come from a partially written file
+            let samples = match audio::run(&path, &config.options.audio) {
+                Ok(samples) => samples,
+                Err(e @ (Error::Io(_) | Error::Symphonia(_))) if attempt < RETRIES => {
+                    tracing::warn!(
+                        "{name}: decode failed ({e}), retry {attempt}/{}",
+                        RETRIES - 1
+                    );
+                    queue.retry(path, attempt + 1, Instant::now());
+                    continue;
+                }
+                Err(e) => {
+                    tracing::error!("{}", Error::audio(&path)(e));
+                    continue;
+                }
+            };
+
+            match transcribe(&model, &path, samples, &config.options) {
+                Ok(output) => {
+                    tracing::info!("{name}: transcript written to {}", output.display());
+                    state.files.insert(name.to_string(), fingerprint);
+                    // The transcript is written, at worst it's made again after a restart
+                    if let Err(e) = state.save(&state_path) {
+                        tracing::error!("Saving {}: {e}", state_path.display());
+                    }
+                }
+                Err(e) => tracing::error!("{}", Error::audio(&path)(e)),
+            }
+        }
+    }
+}
+
+/// Transcribe one decoded file to `<name>.srt`
+fn transcribe(
+    model: &whisper_rs::WhisperContext,
+    path: &Path,
+    samples: Vec<f32>,
+    options: &Options,
+) -> Result<PathBuf> {
+    let mut segments = whisper::run(model, &samples)?;
+    if let Some(diarization) = &options.diarization {
+        diarization.apply(&samples, &mut segments);
+    }
+
+    let output = path.with_extension("srt");
+    std::fs::write(&output, Format::Srt.render(&segments))?;
+
+    Ok(output)
+}
+
+fn is_audio(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| EXTENSIONS.contains(&ext))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("speech2text-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn state_round_trip() {
+        let dir = temp_dir("watch-state");
+        let audio = dir.join("talk.wav");
+        std::fs::write(&audio, b"RIFF").unwrap();
+        let fingerprint = Fingerprint::of(&audio).unwrap();
+        assert_eq!(fingerprint.size, 4);
+
+        let state_path = dir.join(STATE_FILE);
+        assert!(State::load(&state_path).files.is_empty());
+
+        let mut state = State::default();
+        state
+            .files
+            .insert("talk.wav".to_string(), fingerprint.clone());
+        state.save(&state_path).unwrap();
+        assert_eq!(State::load(&state_path).files, state.files);
+
+        // A rewritten file no longer matches what was transcribed
+        std::fs::write(&audio, b"RIFF....").unwrap();
+        assert_ne!(Fingerprint::of(&audio), Some(fingerprint));
+        assert_eq!(Fingerprint::of(&dir.join("missing.wav")), None);
+    }
+
+    #[test]
+    fn files_settle_before_processing() {
+        let start = Instant::now();
+        let path = PathBuf::from("audio/talk.wav");
+        let mut queue = Queue::default();
+
+        queue.changed(path.clone(), start);
+        assert!(queue.due(start + SETTLE / 2).is_empty());
+
+        // Every change restarts the wait
+        queue.changed(path.clone(), start + SETTLE / 2);
+        assert!(queue.due(start + SETTLE).is_empty());
+        assert_eq!(queue.due(start + SETTLE * 3 / 2), [(path.clone(), 1)]);
+        assert!(queue.due(start + SETTLE * 2).is_empty());
+
+        queue.ready(path.clone(), start);
+        assert_eq!(queue.due(start), [(path, 1)]);
+    }
+
+    #[test]
+    fn retries_wait_their_delay() {
+        let start = Instant::now();
+        let path = PathBuf::from("audio/talk.wav");
+        let mut queue = Queue::default();
+
+        queue.retry(path.clone(), 2, start);
+        assert!(queue.due(start + RETRY_DELAY / 2).is_empty());
+        assert_eq!(queue.due(start + RETRY_DELAY), [(path.clone(), 2)]);
+
+        // A change while waiting starts the attempts over
+        queue.retry(path.clone(), 3, start);
+        queue.changed(path.clone(), start);
+        assert_eq!(queue.due(start + SETTLE), [(path, 1)]);
+    }
+}
@@ -1,13 +1,48 @@
 use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
 
 use super::*;
-use std::path::PathBuf;
+use std::path::Path;
+
+/// Decoding setup of [`transcribe_audio`], the one source of its params and of their cache key
+pub struct Decoding {
+    /// Candidates kept by greedy sampling
+    pub best_of: i32,
+    pub language: &'static str,
+}
+
+/// Decoding of every transcription
+pub const DECODING: Decoding = Decoding {
+    best_of: 1,
+    language: "en",
+};
+
+impl Decoding {
+    fn params(&self) -> FullParams<'static, 'static> {
+        let mut params = FullParams::new(SamplingStrategy::Greedy {
+            best_of: self.best_of,
+        });
+        params.set_language(Some(self.language));
+
+        // Console output only, the transcript doesn't change
+        params.set_print_special(false);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_timestamps(false);
+        params
+    }
+
+    /// Stable encoding of the fields, part of the transcript cache key
+    pub fn cache_key(&self) -> String {
+        let Self { best_of, language } = self;
+        format!("sampling=greedy;best_of={best_of};language={language}")
+    }
+}
 
 pub fn init() {
     whisper_rs::install_logging_hooks();
 }
 
-pub fn create_model(path: &PathBuf) -> Result<WhisperContext> {
+pub fn create_model(path: &Path) -> Result<WhisperContext> {
     let model = WhisperContext::new_with_params(
         &path.to_string_lossy(),
         WhisperContextParameters::default(),
@@ -16,26 +51,18 @@ pub fn create_model(path: &PathBuf) -> Result<WhisperContext> {
     Ok(model)
 }
 
-pub fn run(model: &WhisperContext, samples: &[f32]) -> Result<Vec<(i64, i64, String)>> {
+pub fn run(model: &WhisperContext, samples: &[f32]) -> Result<Vec<Segment>> {
     // Start transcribe audio
-    let transcribe = transcribe_audio(&samples, &model)?;
+    let transcribe = transcribe_audio(samples, model)?;
 
     Ok(transcribe)
 }
 
-fn transcribe_audio(samples: &[f32], ctx: &WhisperContext) -> Result<Vec<(i64, i64, String)>> {
+fn transcribe_audio(samples: &[f32], ctx: &WhisperContext) -> Result<Vec<Segment>> {
     let mut state = ctx.create_state()?;
-    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
-
-    // Configure processing parameters
-    params.set_language(Some("en"));
-    params.set_print_special(false);
-    params.set_print_progress(false);
-    params.set_print_realtime(false);
-    params.set_print_timestamps(false);
 
     // Run inference
-    state.full(params, samples)?;
+    state.full(DECODING.params(), samples)?;
 
     // Collect results
     let num_segments = state.full_n_segments()?;
@@ -45,7 +72,12 @@ fn transcribe_audio(samples: &[f32], ctx: &WhisperContext) -> Result<Vec<(i64, i
         let segment = state.full_get_segment_text(i)?;
         let start = state.full_get_segment_t0(i)?;
         let end = state.full_get_segment_t1(i)?;
-        transcript.push((start, end, segment));
+        transcript.push(Segment {
+            start,
+            end,
+            text: segment,
+            speaker: None,
+        });
     }
 
     Ok(transcript)
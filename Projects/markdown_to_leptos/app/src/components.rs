@@ -0,0 +1,3 @@
+mod counter;
+
+pub use counter::*;
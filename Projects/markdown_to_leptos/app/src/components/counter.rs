@@ -0,0 +1,13 @@
+use leptos::prelude::*;
+
+/// Button counting its clicks, embedded in posts with `<Counter initial=3 />`
+#[island]
+pub fn Counter(#[prop(optional)] initial: i32) -> impl IntoView {
+    let (count, set_count) = signal(initial);
+
+    view! {
+        <button class="counter" on:click=move |_| *set_count.write() += 1>
+            "Clicked " {count} " times"
+        </button>
+    }
+}
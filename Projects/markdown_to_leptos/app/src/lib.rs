@@ -5,9 +5,17 @@ use leptos_router::{
     path,
 };
 
+mod components;
+mod live_reload;
 mod pages;
+mod sitemap;
+use live_reload::LiveReload;
 use pages::*;
 
+pub use live_reload::LIVE_RELOAD_PATH;
+pub use pages::{blog_feed, BlogSiteUrl, BLOG_DIR, BLOG_ROUTE};
+pub use sitemap::sitemap;
+
 pub fn shell(options: LeptosOptions) -> impl IntoView {
     view! {
         <!DOCTYPE html>
@@ -16,6 +24,7 @@ pub fn shell(options: LeptosOptions) -> impl IntoView {
                 <meta charset="utf-8" />
                 <meta name="viewport" content="width=device-width, initial-scale=1" />
                 <AutoReload options=options.clone() />
+                <LiveReload />
                 <HashedStylesheet options=options.clone() />
                 <HydrationScripts options=options islands=true islands_router=true />
                 <MetaTags />
@@ -32,14 +41,18 @@ pub fn App() -> impl IntoView {
     provide_meta_context();
 
     view! {
-        <Title text="Welcome to Leptos!" />
+        // Pages without their own title, articles set theirs
+        <Title text="Markdown to Leptos" />
         <Link rel="shortcut icon" type_="image/ico" href="/favicon.ico" />
+        <Link rel="alternate" type_="application/rss+xml" href="/blog/feed.xml" />
         <Stylesheet id="leptos" href="/pkg/markdown_to_leptos.css" />
 
         <Router>
             <nav>
                 <a href="/">"Home"</a>
                 <a href="/blog">"Blog"</a>
+                <a href="/blog/tags">"Tags"</a>
+                <a href="/blog/search">"Search"</a>
             </nav>
 
             <main>
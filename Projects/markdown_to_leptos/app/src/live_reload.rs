@@ -0,0 +1,25 @@
+use leptos::prelude::*;
+
+/// Path of the websocket pushing the posts edited in development
+pub const LIVE_RELOAD_PATH: &str = "/_markdown/live";
+
+/// Swaps the edited post into the page, when the server pushes it for the current path
+const SCRIPT: &str = r#"
+(() => {
+    const scheme = location.protocol === "https:" ? "wss" : "ws";
+    const socket = new WebSocket(`${scheme}://${location.host}${document.currentScript.dataset.path}`);
+    socket.onmessage = (event) => {
+        const [href, ...html] = event.data.split("\n");
+        if (decodeURI(location.pathname).replace(/\/$/, "") !== href) return;
+        const section = document.querySelector("article > section");
+        if (section) section.innerHTML = html.join("\n");
+    };
+})();
+"#;
+
+/// Live preview of the Markdown posts, in debug builds only
+#[component]
+pub fn LiveReload() -> impl IntoView {
+    cfg!(debug_assertions)
+        .then(|| view! { <script data-path=LIVE_RELOAD_PATH inner_html=SCRIPT></script> })
+}
@@ -4,4 +4,6 @@ use leptos_router::{
     path,
 };
 
-markdown::include_md! {}
+use crate::components::Counter;
+
+markdown::include_md! { components = [Counter], toc_highlight = true }
@@ -0,0 +1,21 @@
+use crate::pages::blog_paths;
+
+/// Pages outside the generated blog routes
+const PATHS: &[&str] = &["/"];
+
+/// `sitemap.xml` listing every public page, `site_url` being e.g. `https://example.com`
+pub fn sitemap(site_url: &str) -> String {
+    let site_url = site_url.trim_end_matches('/');
+    let urls = PATHS
+        .iter()
+        .copied()
+        .chain(blog_paths())
+        .map(|path| format!("<url><loc>{site_url}{path}</loc></url>\n"))
+        .collect::<String>();
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n\
+         {urls}</urlset>\n"
+    )
+}
@@ -1,14 +1,51 @@
+use std::collections::BTreeSet;
 use walkdir::WalkDir;
 
+/// Extensions of the images posts can embed
+const IMAGES: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "avif", "svg"];
+
 fn main() {
     println!("cargo:rerun-if-changed=src/lib.rs");
 
-    println!("cargo:rerun-if-changed=../Docs/");
+    // `include_md!` reads any folder of the workspace, so watch every Markdown file
+    let entries = WalkDir::new("..")
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            entry.depth() == 0
+                || !(name.starts_with('.') || name == "target" || name == "node_modules")
+        })
+        .filter_map(Result::ok);
+
+    let mut dirs = BTreeSet::new();
+    let mut images = vec![];
+    for entry in entries.filter(|entry| entry.file_type().is_file()) {
+        let path = entry.path();
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("md") => {
+                println!("cargo:rerun-if-changed={}", path.display());
+                if let Some(dir) = path.parent() {
+                    dirs.insert(dir.to_path_buf());
+                }
+            }
+            Some(ext) if IMAGES.contains(&ext.to_lowercase().as_str()) => {
+                images.push(path.to_path_buf())
+            }
+            _ => {}
+        }
+    }
+
+    // Watching a folder catches new files too, but not for crate folders: cargo would rerun
+    // this script on every change to their sources
+    dirs.retain(|dir| !dir.join("Cargo.toml").exists());
+    for dir in &dirs {
+        println!("cargo:rerun-if-changed={}", dir.display());
+    }
 
-    // Watch every file in the folder recursively
-    for entry in WalkDir::new("../Docs").into_iter().filter_map(Result::ok) {
-        if entry.file_type().is_file() {
-            println!("cargo:rerun-if-changed={}", entry.path().display());
+    // Images of the posts are fingerprinted by the macro, so their content matters too
+    for image in images {
+        if image.ancestors().any(|dir| dirs.contains(dir)) {
+            println!("cargo:rerun-if-changed={}", image.display());
         }
     }
 }
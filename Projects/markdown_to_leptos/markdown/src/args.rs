@@ -0,0 +1,217 @@
+use std::path::Path;
+
+use convert_case::{Case, Casing};
+use proc_macro2::{Ident, Span};
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+use syn::{Expr, ExprLit, Lit, MetaNameValue, Token};
+
+/// Arguments of `include_md!`, e.g. `include_md!(dir = "Docs/guides", route = "guides",
+/// component_prefix = "Guide")`
+pub struct Args {
+    /// Folder of the Markdown files, relative to the workspace root
+    pub dir: String,
+    /// Route of the collection, without slashes at the ends
+    pub route: String,
+    /// Start of every generated name, so collections don't collide
+    pub component_prefix: String,
+    /// Heading of the listing page and title of the feed
+    pub title: String,
+    /// Leptos components the posts may embed, e.g. `Counter`
+    pub components: Vec<String>,
+    /// Whether the table of contents highlights the section being read
+    pub toc_highlight: bool,
+    /// Posts per listing page
+    pub page_size: usize,
+    /// Domains the posts may link to, any when `None`
+    pub allowed_domains: Option<Vec<String>>,
+    /// ES module of mermaid, imported by the pages holding a diagram
+    pub mermaid_url: String,
+}
+
+/// Mermaid from jsdelivr, for sites not serving their own copy
+const MERMAID_URL: &str = "https://cdn.jsdelivr.net/npm/mermaid@11/dist/mermaid.esm.min.mjs";
+
+impl Args {
+    pub fn parse(input: proc_macro2::TokenStream) -> syn::Result<Self> {
+        let mut dir = "Docs".to_string();
+        let mut route = "blog".to_string();
+        let mut component_prefix = "Blog".to_string();
+        let mut title = None;
+        let mut components = vec![];
+        let mut toc_highlight = false;
+        let mut page_size = 10;
+        let mut allowed_domains = None;
+        let mut mermaid_url = MERMAID_URL.to_string();
+
+        let arguments = Punctuated::<MetaNameValue, Token![,]>::parse_terminated.parse2(input)?;
+        for argument in arguments {
+            // `components = [Counter, Chart]`, `allowed_domains = ["github.com"]`,
+            // `toc_highlight = true` and `page_size = 10` aren't strings
+            if argument.path.is_ident("components") {
+                let Expr::Array(array) = &argument.value else {
+                    return Err(syn::Error::new_spanned(
+                        &argument.value,
+                        "expected an array of components, e.g. `[Counter]`",
+                    ));
+                };
+                for element in &array.elems {
+                    let name = match element {
+                        Expr::Path(path) => path.path.get_ident(),
+                        _ => None,
+                    };
+                    let Some(name) = name else {
+                        return Err(syn::Error::new_spanned(
+                            element,
+                            "expected a component name",
+                        ));
+                    };
+                    components.push(name.to_string());
+                }
+                continue;
+            }
+            if argument.path.is_ident("allowed_domains") {
+                let Expr::Array(array) = &argument.value else {
+                    return Err(syn::Error::new_spanned(
+                        &argument.value,
+                        "expected an array of domains, e.g. `[\"github.com\"]`",
+                    ));
+                };
+                let mut domains = vec![];
+                for element in &array.elems {
+                    let Expr::Lit(ExprLit {
+                        lit: Lit::Str(domain),
+                        ..
+                    }) = element
+                    else {
+                        return Err(syn::Error::new_spanned(element, "expected a domain string"));
+                    };
+                    domains.push(domain.value().to_lowercase());
+                }
+                allowed_domains = Some(domains);
+                continue;
+            }
+            if argument.path.is_ident("toc_highlight") {
+                let Expr::Lit(ExprLit {
+                    lit: Lit::Bool(value),
+                    ..
+                }) = &argument.value
+                else {
+                    return Err(syn::Error::new_spanned(
+                        &argument.value,
+                        "expected `true` or `false`",
+                    ));
+                };
+                toc_highlight = value.value;
+                continue;
+            }
+            if argument.path.is_ident("page_size") {
+                let size = match &argument.value {
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Int(value),
+                        ..
+                    }) => value.base10_parse::<usize>().ok().filter(|size| *size > 0),
+                    _ => None,
+                };
+                let Some(size) = size else {
+                    return Err(syn::Error::new_spanned(
+                        &argument.value,
+                        "expected a number of posts above 0",
+                    ));
+                };
+                page_size = size;
+                continue;
+            }
+
+            let value = match &argument.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(value),
+                    ..
+                }) => value,
+                value => return Err(syn::Error::new_spanned(value, "expected a string literal")),
+            };
+
+            let name = argument.path.get_ident().map(Ident::to_string);
+            match name.as_deref() {
+                Some("dir") => {
+                    dir = value.value();
+                    if !Path::new(&dir).is_dir() {
+                        return Err(syn::Error::new_spanned(
+                            value,
+                            format!(
+                                "directory `{dir}` not found, paths are relative to the workspace root"
+                            ),
+                        ));
+                    }
+                }
+                Some("route") => {
+                    route = value.value().trim_matches('/').to_string();
+                    if route.is_empty() {
+                        return Err(syn::Error::new_spanned(value, "route can't be empty"));
+                    }
+                }
+                Some("component_prefix") => {
+                    component_prefix = value.value();
+                    if syn::parse_str::<Ident>(&component_prefix).is_err()
+                        || component_prefix != component_prefix.to_case(Case::Pascal)
+                    {
+                        return Err(syn::Error::new_spanned(
+                            value,
+                            "component_prefix must be a PascalCase identifier",
+                        ));
+                    }
+                }
+                Some("title") => title = Some(value.value()),
+                Some("mermaid_url") => mermaid_url = value.value(),
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        &argument.path,
+                        "unknown argument, expected `dir`, `route`, `component_prefix`, `title`, \
+                         `components`, `toc_highlight`, `page_size`, `allowed_domains` or `mermaid_url`",
+                    ));
+                }
+            }
+        }
+
+        Ok(Self {
+            title: title.unwrap_or_else(|| component_prefix.clone()),
+            dir,
+            route,
+            component_prefix,
+            components,
+            toc_highlight,
+            page_size,
+            allowed_domains,
+            mermaid_url,
+        })
+    }
+
+    /// `{prefix}{name}`, e.g. `BlogPage`
+    pub fn component(&self, name: &str) -> Ident {
+        Ident::new(
+            &format!("{}{name}", self.component_prefix),
+            Span::call_site(),
+        )
+    }
+
+    /// `{prefix}_{name}` in snake case, e.g. `blog_feed`
+    pub fn function(&self, name: &str) -> Ident {
+        let prefix = self.component_prefix.to_case(Case::Snake);
+        Ident::new(&format!("{prefix}_{name}"), Span::call_site())
+    }
+
+    /// `{PREFIX}_{NAME}`, e.g. `BLOG_PATHS`
+    pub fn constant(&self, name: &str) -> Ident {
+        let prefix = self.component_prefix.to_case(Case::UpperSnake);
+        Ident::new(&format!("{prefix}_{name}"), Span::call_site())
+    }
+
+    /// Absolute link to a page of the collection, e.g. `/blog/tags`
+    pub fn href(&self, path: &str) -> String {
+        if path.is_empty() {
+            format!("/{}", self.route)
+        } else {
+            format!("/{}/{path}", self.route)
+        }
+    }
+}
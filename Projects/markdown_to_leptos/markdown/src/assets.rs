@@ -0,0 +1,70 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Copies the images of the posts into the public folder, under names changing with their content
+pub struct Assets {
+    /// Folder served at the site root, e.g. `public`
+    public_dir: PathBuf,
+}
+
+/// Image ready for an `<img>`
+pub struct Image {
+    pub src: String,
+    /// Width and height in pixels, unknown for remote images and formats like SVG
+    pub size: Option<(usize, usize)>,
+}
+
+impl Assets {
+    pub fn new(public_dir: PathBuf) -> Self {
+        Self { public_dir }
+    }
+
+    /// Image at `url`, relative to `dir`, the folder of its post
+    ///
+    /// Remote and absolute URLs are kept as they are, other files are copied to
+    /// `{public_dir}/images/{name}-{hash}.{extension}`.
+    pub fn image(&self, dir: &Path, url: &str) -> Result<Image, String> {
+        let remote = url.contains("://") || url.starts_with("//") || url.starts_with("data:");
+        if remote || url.starts_with('/') {
+            return Ok(Image {
+                src: url.to_string(),
+                size: None,
+            });
+        }
+
+        let path = dir.join(url.split(['?', '#']).next().unwrap_or_default());
+        let data = fs::read(&path)
+            .map_err(|err| format!("image `{url}` not found at `{}`: {err}", path.display()))?;
+
+        // `cat.png` -> `cat-1b2c3d4e.png`
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let mut name = format!("{stem}-{:08x}", fingerprint(&data));
+        if let Some(extension) = path.extension() {
+            name = format!("{name}.{}", extension.to_string_lossy());
+        }
+
+        let images_dir = self.public_dir.join("images");
+        let target = images_dir.join(&name);
+        if !target.exists() {
+            fs::create_dir_all(&images_dir)
+                .and_then(|_| fs::write(&target, &data))
+                .map_err(|err| {
+                    format!("can't copy image `{url}` to `{}`: {err}", target.display())
+                })?;
+        }
+
+        Ok(Image {
+            src: format!("/images/{name}"),
+            size: imagesize::blob_size(&data)
+                .ok()
+                .map(|size| (size.width, size.height)),
+        })
+    }
+}
+
+/// 32-bit FNV-1a hash, stable across builds and Rust versions unlike `DefaultHasher`
+fn fingerprint(data: &[u8]) -> u32 {
+    data.iter().fold(0x811c_9dc5, |hash, &byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
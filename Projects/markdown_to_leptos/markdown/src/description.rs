@@ -1,40 +1,181 @@
 use chrono::{DateTime, Utc};
+use proc_macro2::TokenStream;
 use quote::{ToTokens, quote};
 
-#[derive(Default)]
+use crate::front_matter::FrontMatter;
+use crate::slug::slugify;
+use crate::tags;
+
 pub struct Description {
     pub title: String,
     pub date: DateTime<Utc>,
-    pub tags: String,
+    pub tags: Vec<String>,
+    pub summary: Option<String>,
+    pub draft: bool,
+    /// Route of the collection, e.g. `blog`
+    pub route: String,
+    /// Folders between the collection and the article, e.g. `["rust"]`
+    pub dirs: Vec<String>,
+    /// Route of the article in its folder
     pub path: String,
+    /// Headings of the article, for the search index
+    pub headings: Vec<String>,
+    /// Plain text of the article, for the search index
+    pub text: String,
+    /// First paragraph of the article, for the meta description when there is no summary
+    pub excerpt: Option<String>,
+    /// Number of words of the article
+    pub words: usize,
 }
 
 impl ToTokens for Description {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
         let title = &self.title;
-        let path = &self.path;
+        let href = self.href();
         let date = &self.date.to_string();
-        let tags = &self.tags;
+        let tags = tags::links(&self.route, &self.tags);
+        let summary = self
+            .summary
+            .as_ref()
+            .map(|summary| quote!(<span class="summary">#summary</span>));
+        let draft = self.draft_badge();
         tokens.extend(quote! {
-            <A href=#path>
-                <span class="title">#title</span>
+            <div class="post">
+                <A href=#href>
+                    <span class="title">#title #draft</span>
+                </A>
                 <span class="date">#date</span>
                 <span class="tags">#tags</span>
-            </A>
+                #summary
+            </div>
         })
     }
 }
 
 impl Description {
-    pub fn new(path: String) -> Self {
+    /// `file_name` is the route path unless the front matter sets a slug
+    pub fn new(
+        route: &str,
+        dirs: Vec<String>,
+        file_name: String,
+        front_matter: FrontMatter,
+    ) -> Self {
         Self {
-            title: Default::default(),
-            date: Default::default(),
-            tags: Default::default(),
-            path,
+            title: front_matter.title,
+            date: front_matter.date,
+            tags: front_matter.tags,
+            summary: front_matter.summary,
+            draft: front_matter.draft,
+            route: route.to_string(),
+            dirs,
+            path: front_matter.slug.unwrap_or(file_name),
+            headings: vec![],
+            text: String::new(),
+            excerpt: None,
+            words: 0,
+        }
+    }
+
+    /// Absolute link to the article, so listings can live under any route
+    pub fn href(&self) -> String {
+        let mut href = format!("/{}", self.route);
+        for segment in self.dirs.iter().chain([&self.path]) {
+            href.push('/');
+            href.push_str(segment);
+        }
+        href
+    }
+
+    /// Whether release builds serve the article by now, like `{Prefix}Entry::is_published`
+    pub fn is_published(&self) -> bool {
+        !self.draft && self.date <= Utc::now()
+    }
+
+    /// Title and metadata shown above the article
+    pub fn header(&self) -> TokenStream {
+        let title = &self.title;
+        let date = self.date.to_string();
+        let draft = self.draft_badge();
+        let reading = format!("{} min read", self.reading_time());
+        let words = format!("{} words", self.words);
+
+        let tags = if !self.tags.is_empty() {
+            let tags = tags::links(&self.route, &self.tags);
+            quote!(<span class="tags">#tags</span>)
+        } else {
+            quote!()
+        };
+
+        quote! {
+            <h1>#title #draft</h1>
+            <p class="meta">
+                <span class="date">#date</span>
+                <span class="reading-time">#reading</span>
+                <span class="words">#words</span>
+                #tags
+            </p>
         }
     }
 
+    /// Estimated minutes to read the article, at 200 words a minute
+    pub fn reading_time(&self) -> usize {
+        self.words.div_ceil(200).max(1)
+    }
+
+    /// Other posts sharing tags with this one, the most shared tags first, then the newest
+    pub fn related<'a>(&self, descriptions: &'a [Description]) -> Vec<&'a Description> {
+        let slugs: Vec<String> = self.tags.iter().map(|tag| slugify(tag)).collect();
+        let mut related: Vec<(usize, &Description)> = descriptions
+            .iter()
+            .filter(|description| description.href() != self.href())
+            .map(|description| {
+                let shared = description
+                    .tags
+                    .iter()
+                    .filter(|tag| slugs.contains(&slugify(tag)))
+                    .count();
+                (shared, description)
+            })
+            .filter(|(shared, _)| *shared > 0)
+            .collect();
+        // Stable, so equal counts stay newest first
+        related.sort_by_key(|(shared, _)| std::cmp::Reverse(*shared));
+        related
+            .into_iter()
+            .map(|(_, description)| description)
+            .collect()
+    }
+
+    /// Description for search engines and link previews: the summary, or the start of the first
+    /// paragraph cut at a word under 160 characters
+    pub fn meta_description(&self) -> String {
+        if let Some(summary) = &self.summary {
+            return summary.clone();
+        }
+        let excerpt = self.excerpt.as_deref().unwrap_or_default();
+        if excerpt.chars().count() <= 160 {
+            return excerpt.to_string();
+        }
+
+        let mut description = String::new();
+        for word in excerpt.split(' ') {
+            if description.chars().count() + word.chars().count() + 1 > 159 {
+                break;
+            }
+            if !description.is_empty() {
+                description.push(' ');
+            }
+            description.push_str(word);
+        }
+        description.push('…');
+        description
+    }
+
+    fn draft_badge(&self) -> Option<TokenStream> {
+        self.draft
+            .then(|| quote!(<span class="draft">"Draft"</span>))
+    }
+
     pub fn reverse_as_date(descriptions: &mut [Description]) {
         descriptions.sort_by_key(|description| std::cmp::Reverse(description.date));
     }
@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+
+use convert_case::{Case, Casing};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+
+use crate::args::Args;
+use crate::description::Description;
+use crate::entries;
+
+/// Folder of the collection, nested as a `ParentRoute` with its own index page
+#[derive(Default)]
+pub struct Dir {
+    /// Routes of the articles right in the folder
+    routes: Vec<TokenStream>,
+    dirs: BTreeMap<String, Dir>,
+}
+
+impl Dir {
+    /// Adds the route of an article found in the `dirs` subfolder
+    pub fn insert(&mut self, dirs: &[String], route: TokenStream) {
+        match dirs.split_first() {
+            Some((name, rest)) => self
+                .dirs
+                .entry(name.clone())
+                .or_default()
+                .insert(rest, route),
+            None => self.routes.push(route),
+        }
+    }
+
+    /// Whether `dirs` is a subfolder, so no article can take its route
+    pub fn contains(&self, dirs: &[String]) -> bool {
+        match dirs.split_first() {
+            Some((name, rest)) => self.dirs.get(name).is_some_and(|dir| dir.contains(rest)),
+            None => true,
+        }
+    }
+
+    /// Routes of the folder at `path`, with a `ParentRoute` and an index page per subfolder
+    ///
+    /// Pushes the index pages to `fn_list`, naming them apart from `names`.
+    pub fn routes(
+        &self,
+        args: &Args,
+        descriptions: &[Description],
+        path: &[String],
+        names: &mut Vec<String>,
+        fn_list: &mut Vec<TokenStream>,
+    ) -> Vec<TokenStream> {
+        let mut route_list = self.routes.clone();
+        for (name, dir) in &self.dirs {
+            let path = [path, std::slice::from_ref(name)].concat();
+            let page = index_page(args, descriptions, &path, dir, names, fn_list);
+            let routes = dir.routes(args, descriptions, &path, names, fn_list);
+            route_list.push(quote! {
+                <ParentRoute path=path!(#name) view=|| view!{<Outlet/>}>
+                    <Route path=path!("") view=#page />
+                    #(#routes)*
+                </ParentRoute>
+            });
+        }
+        route_list
+    }
+}
+
+/// `{prefix}Dir{Path}`, listing the subfolders and every article below the folder
+fn index_page(
+    args: &Args,
+    descriptions: &[Description],
+    path: &[String],
+    dir: &Dir,
+    names: &mut Vec<String>,
+    fn_list: &mut Vec<TokenStream>,
+) -> Ident {
+    // `rust/async` -> BlogDirRustAsync
+    let page = unique(
+        names,
+        format!("{}Dir{}", args.component_prefix, pascal(path)),
+    );
+    let name = path.last().map(String::as_str).unwrap_or_default();
+    let breadcrumbs = breadcrumbs(args, &path[..path.len() - 1], name);
+
+    let subdirs = dir.dirs.keys().map(|subdir| {
+        let href = args.href(&[path, std::slice::from_ref(subdir)].concat().join("/"));
+        quote!(<li><A href=#href>#subdir</A></li>)
+    });
+    let subdirs = (!dir.dirs.is_empty()).then(|| quote!(<ul class="dirs">#(#subdirs)*</ul>));
+    let cards = entries::cards(
+        args,
+        descriptions,
+        descriptions
+            .iter()
+            .filter(|description| description.dirs.starts_with(path)),
+    );
+
+    fn_list.push(quote! {
+        #[component]
+        fn #page() -> impl IntoView {
+            view! {
+                #breadcrumbs
+                <h1>#name</h1>
+                #subdirs
+                #cards
+            }
+            .into_any()
+        }
+    });
+    page
+}
+
+/// `{prefix}Breadcrumbs`, the trail of links from the collection down to the current page
+pub fn breadcrumbs_component(args: &Args) -> TokenStream {
+    let breadcrumbs = args.component("Breadcrumbs");
+    quote! {
+        /// Links to the pages above the current one, e.g. `Blog / rust / Intro`
+        #[component]
+        pub fn #breadcrumbs(
+            /// Label and link of every page above, from the collection down
+            trail: &'static [(&'static str, &'static str)],
+            current: &'static str,
+        ) -> impl IntoView {
+            view! {
+                <nav class="breadcrumbs">
+                    {trail
+                        .iter()
+                        .map(|(label, href)| view! {
+                            <A href=*href>{*label}</A>
+                            <span class="separator">"/"</span>
+                        })
+                        .collect_view()}
+                    <span class="current">{current}</span>
+                </nav>
+            }
+        }
+    }
+}
+
+/// Breadcrumbs of a page in the `dirs` folder, whose title is `current`
+pub fn breadcrumbs(args: &Args, dirs: &[String], current: &str) -> TokenStream {
+    let breadcrumbs = args.component("Breadcrumbs");
+    let title = &args.title;
+    let root = args.href("");
+    let trail = (1..=dirs.len()).map(|end| {
+        let name = &dirs[end - 1];
+        let href = args.href(&dirs[..end].join("/"));
+        quote!((#name, #href))
+    });
+    quote!(<#breadcrumbs trail={&[(#title, #root), #(#trail),*]} current=#current />)
+}
+
+/// PascalCase of every path segment, e.g. `rust/my_intro` -> `RustMyIntro`
+pub fn pascal(path: &[String]) -> String {
+    path.iter()
+        .map(|segment| segment.to_case(Case::Pascal))
+        .collect()
+}
+
+/// Unique name, numbered from 2 when paths like `rust/intro` and `rust_intro` meet
+pub fn unique(names: &mut Vec<String>, name: String) -> Ident {
+    let mut unique = name.clone();
+    let mut number = 1;
+    while names.contains(&unique) {
+        number += 1;
+        unique = format!("{name}{number}");
+    }
+    names.push(unique.clone());
+    Ident::new(&unique, Span::call_site())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unique_names() {
+        let mut names = vec!["BlogPostIntro2".to_string()];
+        let mut unique = |name: &str| unique(&mut names, name.to_string()).to_string();
+
+        assert_eq!(unique("BlogPostIntro"), "BlogPostIntro");
+        assert_eq!(unique("BlogPostIntro"), "BlogPostIntro3");
+        assert_eq!(unique("BlogPostIntro"), "BlogPostIntro4");
+        assert_eq!(unique("BlogPostOutro"), "BlogPostOutro");
+    }
+}
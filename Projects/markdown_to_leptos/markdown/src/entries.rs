@@ -0,0 +1,261 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::args::Args;
+use crate::description::Description;
+use crate::tags;
+
+/// `{Prefix}Entry` and `{PREFIX}_ENTRIES`, the posts as listings see them at request time
+///
+/// Drafts and scheduled posts are compiled in, and left out when listed.
+pub fn definitions(args: &Args, descriptions: &[Description]) -> TokenStream {
+    let entry = args.component("Entry");
+    let entries = args.constant("ENTRIES");
+    let visible = args.function("visible");
+
+    let list = descriptions.iter().map(|description| {
+        let date = description.date.timestamp();
+        let draft = description.draft;
+        let title = &description.title;
+        let href = description.href();
+        let tags = description
+            .tags
+            .iter()
+            .map(|tag| tags::href(&args.route, tag));
+        quote! {
+            #entry {
+                date: #date,
+                draft: #draft,
+                title: #title,
+                href: #href,
+                tags: &[#(#tags),*],
+                card: || view! { #description }.into_any(),
+            }
+        }
+    });
+
+    quote! {
+        /// Post of the collection, listed once its date has come
+        pub struct #entry {
+            /// Unix timestamp of the post date
+            pub date: i64,
+            pub draft: bool,
+            pub title: &'static str,
+            /// Absolute path of the post
+            pub href: &'static str,
+            /// Absolute paths of the post's tag pages
+            pub tags: &'static [&'static str],
+            card: fn() -> AnyView,
+        }
+
+        impl #entry {
+            /// Whether listings show the post: drafts only show in debug builds
+            pub fn is_visible(&self) -> bool {
+                (!self.draft || cfg!(debug_assertions)) && self.date <= Self::now()
+            }
+
+            /// Whether the feed and the sitemap list the post
+            pub fn is_published(&self) -> bool {
+                !self.draft && self.date <= Self::now()
+            }
+
+            fn now() -> i64 {
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map_or(0, |since| since.as_secs() as i64)
+            }
+        }
+
+        /// Every post of the collection, newest first
+        pub static #entries: &[#entry] = &[#(#list),*];
+
+        /// Visible posts among the `indices` of the entries
+        fn #visible(indices: &[usize]) -> Vec<&'static #entry> {
+            indices
+                .iter()
+                .map(|&index| &#entries[index])
+                .filter(|entry| entry.is_visible())
+                .collect()
+        }
+    }
+}
+
+/// Indices of `posts` in `descriptions`
+fn indices<'a>(
+    descriptions: &[Description],
+    posts: impl IntoIterator<Item = &'a Description>,
+) -> Vec<usize> {
+    posts
+        .into_iter()
+        .filter_map(|post| {
+            descriptions
+                .iter()
+                .position(|description| std::ptr::eq(description, post))
+        })
+        .collect()
+}
+
+/// Cards of the `posts` visible at request time
+pub fn cards<'a>(
+    args: &Args,
+    descriptions: &[Description],
+    posts: impl IntoIterator<Item = &'a Description>,
+) -> TokenStream {
+    let visible = args.function("visible");
+    let indices = indices(descriptions, posts);
+    quote! {
+        <div class="posts">
+            {#visible(&[#(#indices),*])
+                .into_iter()
+                .map(|entry| (entry.card)())
+                .collect_view()}
+        </div>
+    }
+}
+
+/// Number of the `posts` visible at request time
+pub fn count<'a>(
+    args: &Args,
+    descriptions: &[Description],
+    posts: impl IntoIterator<Item = &'a Description>,
+) -> TokenStream {
+    let visible = args.function("visible");
+    let indices = indices(descriptions, posts);
+    quote!(#visible(&[#(#indices),*]).len())
+}
+
+/// Up to three related posts of `description` visible at request time, `None` without shared tags
+pub fn related(
+    args: &Args,
+    descriptions: &[Description],
+    description: &Description,
+) -> Option<TokenStream> {
+    let related = description.related(descriptions);
+    if related.is_empty() {
+        return None;
+    }
+
+    let visible = args.function("visible");
+    let indices = indices(descriptions, related);
+    Some(quote! {
+        {
+            let related: Vec<_> = #visible(&[#(#indices),*]).into_iter().take(3).collect();
+            (!related.is_empty()).then(|| view! {
+                <aside class="related">
+                    <h2>"Related posts"</h2>
+                    <div class="posts">
+                        {related.into_iter().map(|entry| (entry.card)()).collect_view()}
+                    </div>
+                </aside>
+            })
+        }
+    })
+}
+
+/// `{Prefix}PostNav`, the links to the previous and next visible posts around an article
+pub fn post_nav(args: &Args) -> TokenStream {
+    let post_nav = args.component("PostNav");
+    let entries = args.constant("ENTRIES");
+    quote! {
+        /// Links to the older and newer posts next to the one at `href`, hidden posts skipped
+        #[component]
+        fn #post_nav(href: &'static str) -> impl IntoView {
+            let entries: Vec<_> = #entries.iter().filter(|entry| entry.is_visible()).collect();
+            let position = entries.iter().position(|entry| entry.href == href);
+            // Entries are newest first
+            let next = position
+                .and_then(|position| position.checked_sub(1))
+                .map(|position| entries[position]);
+            let previous = position.and_then(|position| entries.get(position + 1));
+
+            view! {
+                <nav class="post-nav">
+                    {previous.map(|entry| view! {
+                        <A href=entry.href attr:class="previous" attr:rel="prev">
+                            <span class="label">"Previous post"</span>
+                            <span class="title">{entry.title}</span>
+                        </A>
+                    })}
+                    {next.map(|entry| view! {
+                        <A href=entry.href attr:class="next" attr:rel="next">
+                            <span class="label">"Next post"</span>
+                            <span class="title">{entry.title}</span>
+                        </A>
+                    })}
+                </nav>
+            }
+        }
+    }
+}
+
+/// View of a missing page, answered with a 404 status on the server
+pub fn not_found(message: &str) -> TokenStream {
+    quote! {{
+        // `ssr` is the feature of the crate calling `include_md!`
+        #[cfg(feature = "ssr")]
+        if let Some(response) = use_context::<leptos_axum::ResponseOptions>() {
+            response.set_status(http::StatusCode::NOT_FOUND);
+        }
+        view! { <p>#message</p> }.into_any()
+    }}
+}
+
+/// `{Prefix}Page`, the listing of every visible post, `page_size` at a time
+///
+/// Serves the collection route for the first page, and `page/:n` for the others.
+pub fn page(args: &Args) -> TokenStream {
+    let page = args.component("Page");
+    let entries = args.constant("ENTRIES");
+    let title = &args.title;
+    let page_size = args.page_size;
+    let first = args.href("");
+    let pages = args.href("page");
+    let not_found = not_found("Page not found");
+
+    quote! {
+        #[component]
+        pub fn #page() -> impl IntoView {
+            const PAGE_SIZE: usize = #page_size;
+
+            let entries: Vec<_> = #entries.iter().filter(|entry| entry.is_visible()).collect();
+            let pages = entries.len().div_ceil(PAGE_SIZE).max(1);
+            let page = match leptos_router::hooks::use_params_map().get_untracked().get("n") {
+                None => Some(1),
+                Some(n) => n.parse::<usize>().ok().filter(|n| (2..=pages).contains(n)),
+            };
+            let Some(page) = page else {
+                return #not_found;
+            };
+
+            let cards = entries
+                .iter()
+                .skip((page - 1) * PAGE_SIZE)
+                .take(PAGE_SIZE)
+                .map(|entry| (entry.card)())
+                .collect_view();
+            let newer = (page > 1).then(|| {
+                let href = match page {
+                    2 => #first.to_string(),
+                    page => format!("{}/{}", #pages, page - 1),
+                };
+                view! { <A href=href>"Newer posts"</A> }
+            });
+            let older = (page < pages).then(|| {
+                let href = format!("{}/{}", #pages, page + 1);
+                view! { <A href=href>"Older posts"</A> }
+            });
+
+            view! {
+                <h1>#title</h1>
+                <div class="posts">
+                    {cards}
+                </div>
+                <nav class="pager">
+                    {newer}
+                    {older}
+                </nav>
+            }
+            .into_any()
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::args::Args;
+use crate::description::Description;
+
+/// Stands for the site URL while the feed is built, which is only known at runtime
+const SITE_URL: &str = "\u{0}";
+
+/// `{prefix}_feed(site_url)`, the RSS 2.0 feed of every published post
+///
+/// The XML is built at compile time around the site URL, so the function only joins the pieces
+/// of the posts whose date has come.
+pub fn feed(args: &Args, descriptions: &[Description]) -> TokenStream {
+    let title = escape(&args.title);
+    let link = args.href("");
+    let self_link = args.href("feed.xml");
+    let head = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom">
+<channel>
+<title>{title}</title>
+<link>{SITE_URL}{link}</link>
+<description>{title}</description>
+<atom:link href="{SITE_URL}{self_link}" rel="self" type="application/rss+xml"/>
+"#
+    );
+    let head = head.split(SITE_URL);
+
+    // Drafts are left out here, scheduled posts when the feed is requested
+    let items = descriptions
+        .iter()
+        .enumerate()
+        .filter(|(_, description)| !description.draft)
+        .map(|(index, description)| {
+            let link = format!("{SITE_URL}{}", description.href());
+            let mut xml = format!(
+                "<item>\n<title>{}</title>\n<link>{link}</link>\n<guid>{link}</guid>\n<pubDate>{}</pubDate>\n",
+                escape(&description.title),
+                description.date.to_rfc2822(),
+            );
+            if let Some(summary) = &description.summary {
+                xml.push_str(&format!("<description>{}</description>\n", escape(summary)));
+            }
+            for tag in &description.tags {
+                xml.push_str(&format!("<category>{}</category>\n", escape(tag)));
+            }
+            xml.push_str("</item>\n");
+
+            let parts = xml.split(SITE_URL).map(str::to_string).collect::<Vec<_>>();
+            quote!((#index, &[#(#parts),*]))
+        });
+
+    let fn_name = args.function("feed");
+    let entries = args.constant("ENTRIES");
+    quote! {
+        /// RSS feed of the collection, `site_url` being e.g. `https://example.com`
+        pub fn #fn_name(site_url: &str) -> String {
+            const HEAD: &[&str] = &[#(#head),*];
+            const ITEMS: &[(usize, &[&str])] = &[#(#items),*];
+
+            let site_url = site_url.trim_end_matches('/');
+            let mut xml = HEAD.join(site_url);
+            for (index, parts) in ITEMS {
+                if #entries[*index].is_published() {
+                    xml.push_str(&parts.join(site_url));
+                }
+            }
+            xml.push_str("</channel>\n</rss>\n");
+            xml
+        }
+    }
+}
+
+/// `{prefix}_paths()`, every public page of the collection for the sitemap
+pub fn paths(args: &Args, descriptions: &[Description]) -> TokenStream {
+    let mut paths = vec![args.href(""), args.href("tags")];
+    for description in descriptions {
+        for end in 1..=description.dirs.len() {
+            let href = args.href(&description.dirs[..end].join("/"));
+            if !paths.contains(&href) {
+                paths.push(href);
+            }
+        }
+    }
+
+    let fn_name = args.function("paths");
+    let entries = args.constant("ENTRIES");
+    quote! {
+        /// Absolute path of every published page of the collection
+        pub fn #fn_name() -> Vec<&'static str> {
+            let mut paths = vec![#(#paths),*];
+            for entry in #entries.iter().filter(|entry| entry.is_published()) {
+                paths.push(entry.href);
+                for tag in entry.tags {
+                    if !paths.contains(tag) {
+                        paths.push(tag);
+                    }
+                }
+            }
+            paths
+        }
+    }
+}
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -1,42 +1,83 @@
+use std::collections::HashMap;
 use std::fs;
+use std::path::{Path, PathBuf};
 
-use convert_case::{Case, Casing};
 use glob::glob;
-use proc_macro2::{Ident, Span};
 use quote::quote;
 
+mod args;
+mod assets;
 mod description;
+mod dirs;
+mod entries;
+mod feed;
+mod links;
 mod markdown_parser;
+mod mermaid;
+mod meta;
+mod search;
+mod tags;
+mod toc;
+use args::Args;
 use description::Description;
+use dirs::Dir;
+use markdown_core::{front_matter, highlight, slug};
 use markdown_parser::Markdown;
 
+/// Generates a component and a route per Markdown file of a folder, with listing, tag and feed
+///
+/// `include_md! { dir = "Docs", route = "blog", components = [Counter] }`, every argument being
+/// optional. See the README for the others and what the generated pages hold.
 #[proc_macro]
-pub fn include_md(_token_stream: proc_macro::TokenStream) -> proc_macro::TokenStream {
+pub fn include_md(token_stream: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let args = match Args::parse(token_stream.into()) {
+        Ok(args) => args,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
     // Target file
-    let var_name = concat!("Docs/**/*.md");
-    let pattern = var_name;
+    let pattern = format!("{}/**/*.md", glob::Pattern::escape(&args.dir));
 
-    let file_list: Vec<(String, String)> = glob(pattern)
+    let file_list: Vec<(Vec<String>, String, String)> = glob(&pattern)
         .expect("Failed to read glob pattern")
         .filter_map(Result::ok)
         .filter_map(|path| {
+            let dirs = path
+                .parent()?
+                .strip_prefix(&args.dir)
+                .ok()?
+                .iter()
+                .map(|segment| Some(segment.to_str()?.to_string()))
+                .collect::<Option<_>>()?;
             let file_name = path.file_stem()?.to_str()?.to_string();
             let file_path = path.to_str()?.to_string();
-            Some((file_name, file_path))
+            Some((dirs, file_name, file_path))
         })
         .collect();
 
     let mut fn_list = vec![];
-    let mut route_list = vec![];
-    let mut descriptions = vec![];
+    let mut fn_names = vec![];
+    let mut root = Dir::default();
+    let mut descriptions: Vec<Description> = vec![];
+    let mut link_posts = vec![];
+    // Component name, table of contents, body and diagrams of every article, by href
+    let mut articles = HashMap::new();
 
     // Structure for parse markdown
-    let markdown = Markdown::new();
+    let markdown = Markdown::new(args.components.clone(), PathBuf::from("public"));
 
     // Process each markdown file
-    for (file_name, file_path) in file_list {
-        // Convert filename to PascalCase for component names (e.g. "my_blog" -> MyBlog)
-        let fn_name = Ident::new(&file_name.to_case(Case::Pascal), Span::call_site());
+    for (dirs, file_name, file_path) in file_list {
+        // Convert the path to PascalCase for component names
+        // (e.g. "rust/my_blog" -> BlogPostRustMyBlog)
+        let fn_name = dirs::unique(
+            &mut fn_names,
+            format!(
+                "{}Post{}",
+                args.component_prefix,
+                dirs::pascal(&[dirs.as_slice(), std::slice::from_ref(&file_name)].concat())
+            ),
+        );
 
         // Read markdown content
         let file_str = match fs::read_to_string(&file_path) {
@@ -48,67 +89,193 @@ pub fn include_md(_token_stream: proc_macro::TokenStream) -> proc_macro::TokenSt
         };
 
         // Parse markdown and extract metadata
-        let (header, section) = match markdown.parse_markdown(&file_str, file_name.clone()) {
-            Ok(((header, section), description)) => {
-                descriptions.push(description);
-                (header, section)
-            }
+        let dir = Path::new(&file_path).parent().unwrap_or(Path::new(""));
+        let parsed = match markdown.parse_markdown(&file_str, dir) {
+            Ok(parsed) => parsed,
             Err(err) => {
+                let err = format!("{file_path}: {err}");
                 return quote!(compile_error!(#err)).into();
             }
         };
+        let section = parsed.body;
+        let mut description = Description::new(&args.route, dirs, file_name, parsed.front_matter);
+        description.headings = parsed.toc.titles();
+        description.text = parsed.text;
+        description.excerpt = parsed.excerpt;
+        description.words = parsed.words;
+
+        // Slugs can make two files share a route
+        let href = description.href();
+        if descriptions.iter().any(|d| d.href() == href) {
+            let err = format!("{file_path}: route `{href}` is already used");
+            return quote!(compile_error!(#err)).into();
+        }
+
+        let route_path = &description.path;
+
+        // Table of contents next to the article, e.g. `BlogPostMyFirstBlogToc`
+        let toc_name = dirs::unique(&mut fn_names, format!("{fn_name}Toc"));
+        let toc = toc::component(&args, &toc_name, &parsed.toc).map(|toc| {
+            fn_list.push(toc);
+            quote! {
+                <aside>
+                    <#toc_name />
+                </aside>
+            }
+        });
+
+        // Generate route definition, in the `ParentRoute` of its folder
+        // <Route path=path!("my_first_blog") view=BlogPostMyFirstBlog />
+        root.insert(
+            &description.dirs,
+            quote! {
+                <Route path=path!(#route_path) view=#fn_name />
+            },
+        );
+        link_posts.push(links::Post {
+            file_path,
+            href: href.clone(),
+            anchors: parsed.toc.anchors(),
+            links: parsed.links,
+        });
+        articles.insert(href, (fn_name, toc, section, parsed.mermaid));
+        descriptions.push(description);
+    }
+
+    // A folder and an article can't share a route, nor take one of the generated pages
+    for description in &descriptions {
+        let path = [
+            description.dirs.as_slice(),
+            std::slice::from_ref(&description.path),
+        ]
+        .concat();
+        if root.contains(&path) {
+            let err = format!(
+                "route `{}` is both an article and a folder",
+                description.href()
+            );
+            return quote!(compile_error!(#err)).into();
+        }
+        if ["tags", "page", "search", "feed.xml"].contains(&path[0].as_str()) {
+            let err = format!(
+                "route `{}` is reserved for the generated pages",
+                args.href(&path[0])
+            );
+            return quote!(compile_error!(#err)).into();
+        }
+    }
+
+    // Sort blogs by date (newest first), the order every listing, feed and index below relies on
+    Description::reverse_as_date(&mut descriptions);
+
+    // Dead links to the pages of the collection fail the build
+    fn_list.push(links::check(&args, &descriptions, &link_posts));
+
+    // Generate the article components, linking to their neighbours and related posts
+    let entries = args.constant("ENTRIES");
+    let post_nav = args.component("PostNav");
+    let mermaid = args.component("Mermaid");
+    let mut has_mermaid = false;
+    for description in &descriptions {
+        let href = description.href();
+        let Some((fn_name, toc, section, diagrams)) = articles.remove(&href) else {
+            continue;
+        };
+        has_mermaid |= diagrams;
+        let diagrams = diagrams.then(|| quote!(<#mermaid />));
+        let breadcrumbs = dirs::breadcrumbs(&args, &description.dirs, &description.title);
+        let header = description.header();
+        let meta = meta::tags(&args, description);
+        let related = entries::related(&args, &descriptions, description);
 
-        // Generate component function
+        let not_found = entries::not_found("Post not found");
+        // Generate component function, hiding drafts and scheduled posts at request time
         // `my_first_blog` file:
-        // #[component] fn My_First_Blog() -> impl IntoView{}
+        // #[component] fn BlogPostMyFirstBlog() -> impl IntoView{}
         fn_list.push(quote! {
             #[component]
             fn #fn_name() -> impl IntoView {
+                if !#entries.iter().any(|entry| entry.href == #href && entry.is_visible()) {
+                    return #not_found;
+                }
+
+                let meta = #meta;
                 view! {
+                    {meta}
                     <article>
+                        #breadcrumbs
                         <header>
                             #header
                         </header>
+                        #toc
                         <section>
                             #section
                         </section>
+                        #diagrams
+                        <footer>
+                            <#post_nav href=#href />
+                            #related
+                        </footer>
                     </article>
                 }
                 .into_any()
             }
         });
+    }
 
-        // Generate route definition
-        // <Route path=path!("my_first_blog") view=My_First_Blog />
-        route_list.push(quote! {
-            <Route path=path!(#file_name) view=#fn_name />
-        })
+    if has_mermaid {
+        fn_list.push(mermaid::island(&args));
     }
 
-    // Sort blogs by date (newest first)
-    Description::reverse_as_date(&mut descriptions);
+    // Generate the folder index pages and the nested routes of the articles
+    let mut route_list = root.routes(&args, &descriptions, &[], &mut fn_names, &mut fn_list);
+    fn_list.push(dirs::breadcrumbs_component(&args));
+    fn_list.push(meta::site_url(&args));
+    if args.toc_highlight {
+        fn_list.push(toc::highlight_component(&args));
+    }
+
+    // Generate tag index and per-tag pages
+    let (tag_fn_list, tag_route_list) = tags::pages(&args, &descriptions);
+    fn_list.extend(tag_fn_list);
+    route_list.extend(tag_route_list);
+
+    // Generate the search page and its index
+    let (search_fn_list, search_route) = search::page(&args, &descriptions);
+    fn_list.extend(search_fn_list);
+    route_list.push(search_route);
 
-    // Generate blog listing page component
+    // Generate the RSS feed and the paths for the sitemap
+    fn_list.push(feed::feed(&args, &descriptions));
+    fn_list.push(feed::paths(&args, &descriptions));
+
+    // Generate the posts as listed at request time, and the paginated listing page
+    fn_list.push(entries::definitions(&args, &descriptions));
+    fn_list.push(entries::page(&args));
+    fn_list.push(entries::post_nav(&args));
+    let page = args.component("Page");
+
+    // Generate the source folder and route, for the live preview of the server
+    let dir_const = args.constant("DIR");
+    let route_const = args.constant("ROUTE");
+    let (dir, route_path) = (&args.dir, &args.route);
     fn_list.push(quote! {
-        #[component]
-        pub fn BlogPage() -> impl IntoView {
-            view!{
-                <h1>"Blog Page"</h1>
-                <div>
-                    #(#descriptions)*
-                </div>
-            }
-            .into_any()
-        }
+        /// Folder of the Markdown files, relative to the workspace root
+        pub const #dir_const: &str = #dir;
+        /// Route of the collection, without slashes
+        pub const #route_const: &str = #route_path;
     });
 
-    // Generate nested route structure for `BlogRoute`
+    // Generate nested route structure, e.g. `BlogRoute`
+    let route = args.component("Route");
+    let route_path = &args.route;
     fn_list.push(quote! {
         #[component(transparent)]
-        pub fn BlogRoute() -> impl MatchNestedRoutes + Clone {
+        pub fn #route() -> impl MatchNestedRoutes + Clone {
             view! {
-                <ParentRoute path=path!("blog") view=|| view!{<Outlet/>}>
-                    <Route path=path!("") view=BlogPage />
+                <ParentRoute path=path!(#route_path) view=|| view!{<Outlet/>}>
+                    <Route path=path!("") view=#page />
+                    <Route path=path!("page/:n") view=#page />
                     #(#route_list)*
                 </ParentRoute>
             }.into_inner()
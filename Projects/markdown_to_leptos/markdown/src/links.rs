@@ -0,0 +1,295 @@
+use std::collections::{HashMap, HashSet};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::args::Args;
+use crate::description::Description;
+use crate::slug::slugify;
+
+/// Links of a post, checked once every route is known
+pub struct Post {
+    /// Markdown file, for the messages
+    pub file_path: String,
+    pub href: String,
+    /// Anchor ids of the headings
+    pub anchors: Vec<String>,
+    pub links: Vec<String>,
+}
+
+/// Every route `include_md!` generates, with the anchors of the articles
+struct Site {
+    pages: HashSet<String>,
+    anchors: HashMap<String, Vec<String>>,
+}
+
+impl Site {
+    fn new(args: &Args, descriptions: &[&Description], posts: &[&Post]) -> Self {
+        let pages_count = descriptions.len().div_ceil(args.page_size).max(1);
+        let mut pages: HashSet<String> = ["", "tags", "search", "feed.xml"]
+            .into_iter()
+            .map(|path| args.href(path))
+            .chain((2..=pages_count).map(|page| args.href(&format!("page/{page}"))))
+            .collect();
+        for description in descriptions {
+            pages.insert(description.href());
+            for tag in &description.tags {
+                pages.insert(args.href(&format!("tags/{}", slugify(tag))));
+            }
+            for end in 1..=description.dirs.len() {
+                pages.insert(args.href(&description.dirs[..end].join("/")));
+            }
+        }
+
+        let anchors = posts
+            .iter()
+            .map(|post| (post.href.clone(), post.anchors.clone()))
+            .collect();
+        Self { pages, anchors }
+    }
+
+    /// Why `url`, found in the article at `base`, is dead, `None` when it's fine or not ours
+    fn check(&self, args: &Args, base: &str, url: &str) -> Option<String> {
+        let (url, anchor) = match url.split_once('#') {
+            Some((url, anchor)) => (url, Some(anchor)),
+            None => (url, None),
+        };
+        let url = url.split('?').next().unwrap_or_default();
+
+        if is_external(url) {
+            // `mailto:` and the like have no domain to check
+            let host = host(url);
+            if host.is_empty() {
+                return None;
+            }
+            let allowed = args
+                .allowed_domains
+                .as_ref()?
+                .iter()
+                .any(|domain| host == *domain || host.ends_with(&format!(".{domain}")));
+            return (!allowed).then(|| format!("`{host}` isn't in `allowed_domains`"));
+        }
+
+        // Relative links are resolved like the browser does, only the collection's are checked
+        let path = if url.is_empty() {
+            base.to_string()
+        } else if url.starts_with('/') {
+            normalize(url)?
+        } else {
+            let parent = base.rsplit_once('/').map_or("", |(parent, _)| parent);
+            normalize(&format!("{parent}/{url}"))?
+        };
+        let root = args.href("");
+        if path != root && !path.starts_with(&format!("{root}/")) {
+            return None;
+        }
+
+        if !self.pages.contains(&path) {
+            return Some(format!("no page at `{path}`"));
+        }
+        match (anchor, self.anchors.get(&path)) {
+            (Some(anchor), Some(anchors)) if !anchors.iter().any(|id| id == anchor) => {
+                Some(format!("no heading `#{anchor}` in `{path}`"))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Whether the URL leaves the site, or isn't a page at all like `mailto:`
+fn is_external(url: &str) -> bool {
+    url.starts_with("//")
+        || url.split_once(':').is_some_and(|(scheme, _)| {
+            !scheme.is_empty()
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        })
+}
+
+/// Lowercase host of an external URL, empty for `mailto:` and the like
+fn host(url: &str) -> String {
+    let Some((_, rest)) = url.split_once("//") else {
+        return String::new();
+    };
+    let authority = rest.split('/').next().unwrap_or_default();
+    let host = authority.rsplit('@').next().unwrap_or_default();
+    host.split(':').next().unwrap_or_default().to_lowercase()
+}
+
+/// Absolute path without `.`, `..` and trailing slashes, `None` when it climbs above the root
+///
+/// `/blog/rust/../intro/` -> `/blog/intro`
+fn normalize(path: &str) -> Option<String> {
+    let mut segments: Vec<&str> = vec![];
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop()?;
+            }
+            segment => segments.push(segment),
+        }
+    }
+    Some(format!("/{}", segments.join("/")))
+}
+
+/// Checks the links of every post against the generated routes and heading anchors
+///
+/// Dead links fail the build, or warn with the `link-warnings` feature.
+pub fn check(args: &Args, descriptions: &[Description], posts: &[Post]) -> TokenStream {
+    let messages = dead_links(args, descriptions, posts);
+
+    if cfg!(feature = "link-warnings") {
+        // Stable proc macros can't warn, using a deprecated item can
+        quote! {
+            #(
+                const _: () = {
+                    #[deprecated(note = #messages)]
+                    struct DeadLink;
+                    let _ = DeadLink;
+                };
+            )*
+        }
+    } else {
+        quote!(#(compile_error!(#messages);)*)
+    }
+}
+
+/// Message of every dead link
+///
+/// Published posts may only link to published ones, drafts and scheduled posts to any, as debug
+/// builds show them all.
+fn dead_links(args: &Args, descriptions: &[Description], posts: &[Post]) -> Vec<String> {
+    let published: HashSet<String> = descriptions
+        .iter()
+        .filter(|description| description.is_published())
+        .map(Description::href)
+        .collect();
+    let is_published = |post: &&Post| published.contains(&post.href);
+
+    let all = Site::new(
+        args,
+        &descriptions.iter().collect::<Vec<_>>(),
+        &posts.iter().collect::<Vec<_>>(),
+    );
+    let released = Site::new(
+        args,
+        &descriptions
+            .iter()
+            .filter(|description| description.is_published())
+            .collect::<Vec<_>>(),
+        &posts.iter().filter(is_published).collect::<Vec<_>>(),
+    );
+
+    let mut messages = vec![];
+    for post in posts {
+        for url in &post.links {
+            let reason = if is_published(&post) {
+                let reason = released.check(args, &post.href, url);
+                match all.check(args, &post.href, url) {
+                    None => reason.map(|reason| {
+                        format!("{reason} once drafts and scheduled posts are hidden")
+                    }),
+                    Some(_) => reason,
+                }
+            } else {
+                all.check(args, &post.href, url)
+            };
+            if let Some(reason) = reason {
+                messages.push(format!("{}: dead link `{url}`, {reason}", post.file_path));
+            }
+        }
+    }
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+
+    use super::*;
+    use crate::markdown_parser::tests::parse;
+
+    /// Collection holding `/blog/rust/intro`, tagged `Rust`, with a `#setup` heading
+    fn site(args: &Args) -> Site {
+        let mut parsed = parse("## Setup\n").unwrap();
+        parsed.front_matter.tags = vec!["Rust".to_string()];
+        let description = Description::new(
+            &args.route,
+            vec!["rust".to_string()],
+            "intro".to_string(),
+            parsed.front_matter,
+        );
+        let post = Post {
+            file_path: "Docs/rust/intro.md".to_string(),
+            href: description.href(),
+            anchors: parsed.toc.anchors(),
+            links: parsed.links,
+        };
+        Site::new(args, &[&description], &[&post])
+    }
+
+    #[test]
+    fn internal_links() {
+        let args = Args::parse(quote!()).unwrap();
+        let site = site(&args);
+        let check = |url| site.check(&args, "/blog/rust/intro", url);
+
+        assert_eq!(check("#setup"), None);
+        assert_eq!(check("../tags/rust"), None);
+        assert_eq!(check("/blog/rust/"), None);
+        assert_eq!(check("/about"), None);
+        assert_eq!(check("https://example.com"), None);
+        assert_eq!(
+            check("outro").as_deref(),
+            Some("no page at `/blog/rust/outro`")
+        );
+        assert_eq!(
+            check("/blog/rust/intro#install").as_deref(),
+            Some("no heading `#install` in `/blog/rust/intro`")
+        );
+    }
+
+    #[test]
+    fn allowed_domains() {
+        let args = Args::parse(quote!(allowed_domains = ["github.com"])).unwrap();
+        let site = site(&args);
+        let check = |url| site.check(&args, "/blog/rust/intro", url);
+
+        assert_eq!(check("https://github.com/leptos-rs/leptos"), None);
+        assert_eq!(check("https://docs.github.com"), None);
+        assert_eq!(check("mailto:me@example.com"), None);
+        assert_eq!(
+            check("https://example.com/page").as_deref(),
+            Some("`example.com` isn't in `allowed_domains`")
+        );
+    }
+
+    #[test]
+    fn drafts_have_no_page() {
+        let args = Args::parse(quote!(page_size = 1)).unwrap();
+        let descriptions = ["intro", "outro"].map(|name| {
+            let mut parsed = parse("").unwrap();
+            parsed.front_matter.draft = name == "outro";
+            Description::new(&args.route, vec![], name.to_string(), parsed.front_matter)
+        });
+        let links = ["/blog/outro", "/blog/page/2", "/blog/intro"];
+        let posts = descriptions.iter().map(|description| Post {
+            file_path: format!("Docs/{}.md", description.path),
+            href: description.href(),
+            anchors: vec![],
+            links: links.map(String::from).to_vec(),
+        });
+
+        assert_eq!(
+            dead_links(&args, &descriptions, &posts.collect::<Vec<_>>()),
+            [
+                "Docs/intro.md: dead link `/blog/outro`, no page at `/blog/outro` once drafts \
+                 and scheduled posts are hidden",
+                "Docs/intro.md: dead link `/blog/page/2`, no page at `/blog/page/2` once drafts \
+                 and scheduled posts are hidden",
+            ]
+        );
+    }
+}
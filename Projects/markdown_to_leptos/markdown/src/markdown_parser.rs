@@ -1,153 +1,554 @@
-use chrono::{DateTime, NaiveDateTime, Utc};
-use comrak::nodes::{AstNode, NodeValue};
-use comrak::{Arena, Options, parse_document};
-use proc_macro2::{Ident, Span, TokenStream};
+use std::path::{Path, PathBuf};
+
+use comrak::nodes::{AstNode, ListType, NodeValue, TableAlignment};
+use comrak::{Arena, Options, format_html, parse_document};
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
 use quote::quote;
 
-use crate::description::Description;
+use crate::assets::Assets;
+use crate::front_matter::{Format, FrontMatter};
+use crate::highlight::{CodeInfo, Highlighter};
+use crate::toc::Toc;
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+/// Markdown file turned into `view!` nodes
+pub struct Parsed {
+    pub body: TokenStream,
+    pub toc: Toc,
+    pub front_matter: FrontMatter,
+    /// Plain text of the prose, for the search index
+    pub text: String,
+    /// Plain text of the first paragraph, describing the post when it has no summary
+    pub excerpt: Option<String>,
+    /// Number of words of the prose, code blocks left out
+    pub words: usize,
+    /// Whether the post has a ```mermaid diagram, so its page loads mermaid
+    pub mermaid: bool,
+    /// URL of every link, for the link check
+    pub links: Vec<String>,
+}
 
 pub struct Markdown<'a> {
     options: Options<'a>,
+    highlighter: Highlighter,
+    /// Leptos components the posts may use, e.g. `Counter`
+    components: Vec<String>,
+    assets: Assets,
 }
 
 impl Markdown<'_> {
-    pub fn new() -> Self {
-        let mut options = Options::default();
-        options.extension.table = true;
-
-        Self { options }
+    /// `public_dir` is the folder served at the site root, receiving the images of the posts
+    pub fn new(components: Vec<String>, public_dir: PathBuf) -> Self {
+        Self {
+            options: markdown_core::options(),
+            highlighter: Highlighter::new(),
+            components,
+            assets: Assets::new(public_dir),
+        }
     }
 
-    /// Parses markdown content and extracts both rendered body and metadata
-    pub fn parse_markdown(
-        &self,
-        md_text: &str,
-        path: String,
-    ) -> Result<((TokenStream, TokenStream), Description), String> {
-        let mut description = Description::new(path);
+    /// Parses markdown content and extracts both rendered body and front matter metadata
+    ///
+    /// Relative image paths are resolved from `dir`, the folder of the file.
+    pub fn parse_markdown(&self, md_text: &str, dir: &Path) -> Result<Parsed, String> {
+        let format = Format::detect(md_text);
+        let mut options = self.options.clone();
+        options.extension.front_matter_delimiter = Some(format.delimiter().to_string());
 
         let arena = Arena::new();
-        let root = parse_document(&arena, md_text, &self.options);
-        let body = Self::parse_nodes(root, &mut description);
+        let root = parse_document(&arena, md_text, &options);
 
-        Ok((body, description))
+        let front_matter = FrontMatter::from_document(root, format)?;
+
+        // Read before the nodes are rendered, which rewrites the images of inline HTML
+        let text = Self::prose(root);
+        let excerpt = Self::excerpt(root);
+
+        let mut toc = Toc::default();
+        let body = self.parse_nodes(root, dir, &mut toc)?;
+
+        Ok(Parsed {
+            body,
+            toc,
+            front_matter,
+            words: text.split_whitespace().count(),
+            text,
+            excerpt,
+            mermaid: root
+                .descendants()
+                .any(|node| match &node.data.borrow().value {
+                    NodeValue::CodeBlock(code_block) => is_mermaid(&code_block.info),
+                    _ => false,
+                }),
+            links: root
+                .descendants()
+                .filter_map(|node| match &node.data.borrow().value {
+                    NodeValue::Link(link) => Some(link.url.clone()),
+                    _ => None,
+                })
+                .collect(),
+        })
     }
 
-    /// Recursively processes markdown AST nodes to generate view components
+    /// Recursively processes markdown AST nodes to generate view components, collecting the
+    /// headings into `toc`
     fn parse_nodes<'a>(
+        &self,
         node: &'a AstNode<'a>,
-        description: &mut Description,
-    ) -> (TokenStream, TokenStream) {
-        let mut header_children = vec![];
-        let mut section_children = vec![];
-
+        dir: &Path,
+        toc: &mut Toc,
+    ) -> Result<TokenStream, String> {
         // Process child nodes
-        for n in node.children() {
-            let (header, section) = Self::parse_nodes(n, description);
-            header_children.push(header);
-            section_children.push(section);
-        }
+        let children = node
+            .children()
+            .map(|child| self.parse_nodes(child, dir, toc))
+            .collect::<Result<Vec<_>, _>>()?;
 
-        match &node.data.borrow().value {
-            // Document root - combine all child nodes
-            NodeValue::Document => (quote!(#(#header_children)*), quote!(#(#section_children)*)),
+        let tokens = match &node.data.borrow().value {
+            // Document root - combine all child nodes, footnotes last
+            NodeValue::Document => {
+                let (footnotes, blocks): (Vec<_>, Vec<_>) =
+                    node.children().zip(children).partition(|(child, _)| {
+                        matches!(child.data.borrow().value, NodeValue::FootnoteDefinition(_))
+                    });
+                let blocks = blocks.into_iter().map(|(_, block)| block);
+                let footnotes = footnotes.into_iter().map(|(_, footnote)| footnote);
 
-            // Text  node - render directly
-            NodeValue::Text(text) => {
-                let text = text.clone();
-                (quote!(), quote!(#text))
+                if footnotes.len() == 0 {
+                    quote!(#(#blocks)*)
+                } else {
+                    quote! {
+                        #(#blocks)*
+                        <section class="footnotes">
+                            <ol>#(#footnotes)*</ol>
+                        </section>
+                    }
+                }
+            }
+
+            // Already read into the description
+            NodeValue::FrontMatter(_) => quote!(),
+
+            NodeValue::BlockQuote | NodeValue::MultilineBlockQuote(_) => {
+                quote!(<blockquote>#(#children)*</blockquote>)
+            }
+
+            NodeValue::List(list) => match list.list_type {
+                ListType::Bullet => quote!(<ul>#(#children)*</ul>),
+                ListType::Ordered if list.start == 1 => quote!(<ol>#(#children)*</ol>),
+                ListType::Ordered => {
+                    let start = list.start.to_string();
+                    quote!(<ol start=#start>#(#children)*</ol>)
+                }
+            },
+
+            NodeValue::Item(_) => quote!(<li>#(#children)*</li>),
+
+            NodeValue::TaskItem(symbol) => {
+                let checked = symbol.is_some();
+                quote! {
+                    <li class="task-list-item">
+                        <input type="checkbox" disabled=true checked=#checked />
+                        #(#children)*
+                    </li>
+                }
+            }
+
+            NodeValue::DescriptionList => quote!(<dl>#(#children)*</dl>),
+            NodeValue::DescriptionItem(_) => quote!(#(#children)*),
+            NodeValue::DescriptionTerm => quote!(<dt>#(#children)*</dt>),
+            NodeValue::DescriptionDetails => quote!(<dd>#(#children)*</dd>),
+
+            // Highlighted at compile time, the spans are static HTML
+            // ```leptos fences hold a `view!` snippet
+            NodeValue::CodeBlock(code_block)
+                if code_block.info.split_whitespace().next() == Some("leptos") =>
+            {
+                self.component(&code_block.literal)?
+            }
+
+            // ```mermaid fences are drawn in the browser by the mermaid island
+            NodeValue::CodeBlock(code_block) if is_mermaid(&code_block.info) => {
+                let literal = &code_block.literal;
+                quote!(<pre class="mermaid">#literal</pre>)
+            }
+
+            NodeValue::CodeBlock(code_block) => {
+                let info = CodeInfo::parse(&code_block.info)?;
+                let html = self.highlighter.highlight(&code_block.literal, &info)?;
+                let class = info.class();
+                quote!(<pre class=#class><code inner_html=#html></code></pre>)
             }
 
-            // HTML comment metadata (e.g. <!-- Date: ... -->)
             NodeValue::HtmlBlock(block) => {
-                if block.literal.starts_with("<!--") && block.literal.ends_with("-->\n") {
-                    let comment_content = &block.literal[4..block.literal.len() - 4].trim();
-
-                    // Extract date from comment
-                    if let Some(date) = comment_content.strip_prefix("Date:") {
-                        let naive = NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S")
-                            .unwrap_or_default();
-                        description.date = DateTime::<Utc>::from_naive_utc_and_offset(naive, Utc);
-                    }
-                    // Extract tags from comment
-                    if let Some(tags) = comment_content.strip_prefix("Tags:") {
-                        description.tags = tags.to_string();
-                    }
+                let html = block.literal.trim();
+                // Comments have nothing to show
+                if html.starts_with("<!--") && html.ends_with("-->") {
+                    quote!()
+                } else if is_component(html) {
+                    self.component(html)?
+                } else {
+                    quote!(<div inner_html=#html></div>)
                 }
+            }
 
-                (quote!(), quote!())
+            NodeValue::Paragraph => {
+                // Tight lists don't wrap their items in paragraphs
+                let tight = node
+                    .parent()
+                    .and_then(|parent| parent.parent())
+                    .is_some_and(|list| match &list.data.borrow().value {
+                        NodeValue::List(list) => list.tight,
+                        NodeValue::DescriptionItem(item) => item.tight,
+                        _ => false,
+                    });
+
+                if !tight {
+                    self.element(node, dir, "p", quote!(), &children)?
+                } else if let Some(html) = self.inline_html(node, dir)? {
+                    quote!(<span inner_html=#html></span>)
+                } else {
+                    quote!(#(#children)*)
+                }
             }
 
-            // Heading with metadata extraction
+            // Anchor ids from the heading text, e.g. `<h2 id="getting-started">`
             NodeValue::Heading(node_heading) => {
-                let level = node_heading.level;
-                let tag = Ident::new(&format!("h{level}"), Span::call_site());
-
-                match level {
-                    // Handle H1 as header with metadata
-                    1 => {
-                        if description.title.is_empty() {
-                            let title_text: String = node
-                                .children()
-                                .filter_map(|child| {
-                                    if let NodeValue::Text(text) = &child.data.borrow().value {
-                                        Some(text.clone())
-                                    } else {
-                                        None
-                                    }
-                                })
-                                .collect();
-                            description.title = title_text;
-                        }
-
-                        let date = {
-                            let date_str = description.date.to_string();
-                            quote!(<span class="date">#date_str</span>)
-                        };
-
-                        let tags = if !description.tags.is_empty() {
-                            let tags_str = description.tags.clone();
-                            quote!(<span class="tags">#tags_str</span>)
-                        } else {
-                            quote!()
-                        };
-
-                        (
-                            quote! {
-                                <#tag>#(#section_children)*</#tag>
-                                <p class="meta">
-                                    #date
-                                    #tags
-                                </p>
-                            },
-                            quote!(),
-                        )
-                    }
-                    _ => (
-                        quote!(),
-                        quote! {
-                                <#tag>#(#section_children)*</#tag>
-                        },
-                    ),
+                let id = toc.add(node_heading.level, Self::plain_text(node));
+                self.element(
+                    node,
+                    dir,
+                    &format!("h{}", node_heading.level),
+                    quote!(id=#id),
+                    &children,
+                )?
+            }
+
+            NodeValue::ThematicBreak => quote!(<hr />),
+
+            NodeValue::FootnoteDefinition(footnote) => {
+                let id = format!("fn-{}", footnote.name);
+                // One link back to every reference
+                let backrefs = (1..=footnote.total_references.max(1)).map(|n| {
+                    let (href, label) = match n {
+                        1 => (format!("#fnref-{}", footnote.name), "↩".to_string()),
+                        n => (format!("#fnref-{}-{n}", footnote.name), format!("↩{n}")),
+                    };
+                    quote!(<a href=#href class="footnote-backref">#label</a>)
+                });
+
+                quote! {
+                    <li id=#id>
+                        #(#children)*
+                        #(#backrefs)*
+                    </li>
                 }
             }
 
-            // Paragraph wrapping — belongs to section content
-            NodeValue::Paragraph => {
-                let content = quote! {
-                    <p>
-                        #(#section_children)*
-                    </p>
+            NodeValue::Table(_) => {
+                let (head, body): (Vec<_>, Vec<_>) =
+                    node.children().zip(children).partition(|(row, _)| {
+                        matches!(row.data.borrow().value, NodeValue::TableRow(true))
+                    });
+                let head = head.into_iter().map(|(_, row)| row);
+                let body = body.into_iter().map(|(_, row)| row).collect::<Vec<_>>();
+                let body = (!body.is_empty()).then(|| quote!(<tbody>#(#body)*</tbody>));
+
+                quote! {
+                    <table>
+                        <thead>#(#head)*</thead>
+                        #body
+                    </table>
+                }
+            }
+
+            NodeValue::TableRow(_) => quote!(<tr>#(#children)*</tr>),
+
+            NodeValue::TableCell => {
+                let row = node.parent();
+                let header = row.is_some_and(|row| {
+                    matches!(row.data.borrow().value, NodeValue::TableRow(true))
+                });
+                let column = node.preceding_siblings().count() - 1;
+                let alignment = row.and_then(|row| row.parent()).and_then(|table| {
+                    match &table.data.borrow().value {
+                        NodeValue::Table(table) => table.alignments.get(column).copied(),
+                        _ => None,
+                    }
+                });
+
+                let style = match alignment {
+                    Some(TableAlignment::Left) => quote!(style = "text-align: left"),
+                    Some(TableAlignment::Center) => quote!(style = "text-align: center"),
+                    Some(TableAlignment::Right) => quote!(style = "text-align: right"),
+                    Some(TableAlignment::None) | None => quote!(),
+                };
+
+                self.element(
+                    node,
+                    dir,
+                    if header { "th" } else { "td" },
+                    style,
+                    &children,
+                )?
+            }
+
+            // Text  node - render directly
+            NodeValue::Text(text) | NodeValue::EscapedTag(text) => quote!(#text),
+
+            NodeValue::SoftBreak => quote!("\n"),
+            NodeValue::LineBreak => quote!(<br />),
+
+            NodeValue::Code(code) => {
+                let literal = &code.literal;
+                quote!(<code>#literal</code>)
+            }
+
+            // Only reached outside paragraphs and headings, which render their inline HTML whole
+            NodeValue::HtmlInline(html) | NodeValue::Raw(html) => {
+                quote!(<span inner_html=#html></span>)
+            }
+
+            NodeValue::Emph => quote!(<em>#(#children)*</em>),
+            NodeValue::Strong => quote!(<strong>#(#children)*</strong>),
+            NodeValue::Strikethrough => quote!(<del>#(#children)*</del>),
+            NodeValue::Superscript => quote!(<sup>#(#children)*</sup>),
+            NodeValue::Subscript => quote!(<sub>#(#children)*</sub>),
+            NodeValue::Underline => quote!(<u>#(#children)*</u>),
+            NodeValue::SpoileredText => quote!(<span class="spoiler">#(#children)*</span>),
+            NodeValue::Escaped => quote!(#(#children)*),
+
+            NodeValue::Link(link) => {
+                let url = &link.url;
+                let title = (!link.title.is_empty()).then(|| {
+                    let title = &link.title;
+                    quote!(title=#title)
+                });
+                quote!(<a href=#url #title>#(#children)*</a>)
+            }
+
+            NodeValue::WikiLink(link) => {
+                let url = &link.url;
+                quote!(<a href=#url data-wikilink="true">#(#children)*</a>)
+            }
+
+            // Sized and lazily loaded, so the page doesn't jump as images come in
+            NodeValue::Image(link) => {
+                let image = self.assets.image(dir, &link.url)?;
+                let src = &image.src;
+                let alt = Self::plain_text(node);
+                let title = (!link.title.is_empty()).then(|| {
+                    let title = &link.title;
+                    quote!(title=#title)
+                });
+                let size = image.size.map(|(width, height)| {
+                    let (width, height) = (width.to_string(), height.to_string());
+                    quote!(width=#width height=#height)
+                });
+                quote!(<img src=#src alt=#alt #title #size loading="lazy" decoding="async" />)
+            }
+
+            NodeValue::FootnoteReference(reference) => {
+                let href = format!("#fn-{}", reference.name);
+                let id = match reference.ref_num {
+                    1 => format!("fnref-{}", reference.name),
+                    n => format!("fnref-{}-{n}", reference.name),
                 };
+                let label = reference.ix.to_string();
+                quote!(<sup class="footnote-ref"><a href=#href id=#id>#label</a></sup>)
+            }
+
+            // MathML rendered at compile time, `$$display$$` math as a block
+            NodeValue::Math(math) => {
+                let html = markdown_core::math::mathml(&math.literal, math.display_math)?;
+                let class = markdown_core::math::class(math.display_math);
+                quote!(<span class=#class inner_html=#html></span>)
+            }
 
-                (quote!(), content)
+            NodeValue::Alert(alert) => {
+                let class = format!("markdown-alert {}", alert.alert_type.css_class());
+                let title = alert
+                    .title
+                    .clone()
+                    .unwrap_or_else(|| alert.alert_type.default_title());
+                quote! {
+                    <div class=#class>
+                        <p class="markdown-alert-title">#title</p>
+                        #(#children)*
+                    </div>
+                }
             }
+        };
+
+        Ok(tokens)
+    }
+
+    /// Leptos view of a component block like `<Counter initial=3 />`, checking every component
+    /// is one of `components`
+    fn component(&self, source: &str) -> Result<TokenStream, String> {
+        let tokens: TokenStream = source
+            .parse()
+            .map_err(|err| format!("invalid component block `{}`: {err}", source.trim()))?;
 
-            // Warning node while compiling
-            node => {
-                eprintln!("Warning: Unhandled node encountered: {:?}", node);
-                (quote!(), quote!())
+        // Tags like `<Counter` and `</Counter`, leaving expressions in braces alone
+        let tokens_list: Vec<TokenTree> = tokens.clone().into_iter().collect();
+        for (index, token) in tokens_list.iter().enumerate() {
+            let TokenTree::Punct(punct) = token else {
+                continue;
+            };
+            if punct.as_char() != '<' {
+                continue;
+            }
+            let name = match tokens_list.get(index + 1) {
+                Some(TokenTree::Punct(slash)) if slash.as_char() == '/' => {
+                    tokens_list.get(index + 2)
+                }
+                next => next,
+            };
+            if let Some(TokenTree::Ident(name)) = name {
+                let name = name.to_string();
+                if is_component(&format!("<{name}")) && !self.components.contains(&name) {
+                    return Err(format!(
+                        "unknown component `{name}`, add it to `components = [...]` of `include_md!`"
+                    ));
+                }
             }
         }
+
+        Ok(tokens)
     }
+
+    /// `<tag attributes>children</tag>`, with the children rendered by comrak when they contain
+    /// inline HTML, since an opening and a closing tag can't be split into `view!` nodes
+    fn element<'a>(
+        &self,
+        node: &'a AstNode<'a>,
+        dir: &Path,
+        tag: &str,
+        attributes: TokenStream,
+        children: &[TokenStream],
+    ) -> Result<TokenStream, String> {
+        let tag = Ident::new(tag, Span::call_site());
+        Ok(match self.inline_html(node, dir)? {
+            Some(html) => quote!(<#tag #attributes inner_html=#html></#tag>),
+            None => quote!(<#tag #attributes>#(#children)*</#tag>),
+        })
+    }
+
+    /// HTML of the node's children, if any of them is inline HTML
+    ///
+    /// Images and math become inline HTML first, so comrak renders them like their `view!` nodes.
+    fn inline_html<'a>(&self, node: &'a AstNode<'a>, dir: &Path) -> Result<Option<String>, String> {
+        let has_html = node
+            .descendants()
+            .any(|n| matches!(n.data.borrow().value, NodeValue::HtmlInline(_)));
+        if !has_html {
+            return Ok(None);
+        }
+
+        for descendant in node.descendants().skip(1).collect::<Vec<_>>() {
+            let html = match &descendant.data.borrow().value {
+                NodeValue::Image(link) => {
+                    let image = self.assets.image(dir, &link.url)?;
+                    let mut html = format!(
+                        "<img src=\"{}\" alt=\"{}\"",
+                        escape(&image.src),
+                        escape(&Self::plain_text(descendant))
+                    );
+                    if !link.title.is_empty() {
+                        html.push_str(&format!(" title=\"{}\"", escape(&link.title)));
+                    }
+                    if let Some((width, height)) = image.size {
+                        html.push_str(&format!(" width=\"{width}\" height=\"{height}\""));
+                    }
+                    html + " loading=\"lazy\" decoding=\"async\" />"
+                }
+                NodeValue::Math(math) => {
+                    let html = markdown_core::math::mathml(&math.literal, math.display_math)?;
+                    let class = markdown_core::math::class(math.display_math);
+                    format!("<span class=\"{class}\">{html}</span>")
+                }
+                _ => continue,
+            };
+            // The alt text is in the HTML now
+            for child in descendant.children().collect::<Vec<_>>() {
+                child.detach();
+            }
+            descendant.data.borrow_mut().value = NodeValue::HtmlInline(html);
+        }
+
+        let mut html = vec![];
+        for child in node.children() {
+            format_html(child, &self.options, &mut html).map_err(|err| err.to_string())?;
+        }
+        String::from_utf8(html)
+            .map(Some)
+            .map_err(|err| err.to_string())
+    }
+
+    /// Text of the paragraphs, headings, lists and tables, leaving code blocks and HTML out
+    fn prose<'a>(root: &'a AstNode<'a>) -> String {
+        let mut text = String::new();
+        for node in root.descendants() {
+            match &node.data.borrow().value {
+                NodeValue::Text(literal) => text.push_str(literal),
+                NodeValue::Code(code) => text.push_str(&code.literal),
+                // Blocks and breaks separate words
+                NodeValue::Paragraph
+                | NodeValue::Heading(_)
+                | NodeValue::TableCell
+                | NodeValue::SoftBreak
+                | NodeValue::LineBreak => text.push(' '),
+                _ => {}
+            }
+        }
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    /// Text of the first top-level paragraph, `None` when the post only has other blocks
+    fn excerpt<'a>(root: &'a AstNode<'a>) -> Option<String> {
+        root.children()
+            .filter(|node| matches!(node.data.borrow().value, NodeValue::Paragraph))
+            .map(|paragraph| {
+                Self::plain_text(paragraph)
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .find(|text| !text.is_empty())
+    }
+
+    /// Text content of the node, e.g. an image's alt text
+    fn plain_text<'a>(node: &'a AstNode<'a>) -> String {
+        node.descendants()
+            .filter_map(|n| match &n.data.borrow().value {
+                NodeValue::Text(text) => Some(text.clone()),
+                NodeValue::Code(code) => Some(code.literal.clone()),
+                NodeValue::SoftBreak | NodeValue::LineBreak => Some(" ".to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Whether a fenced code block is a mermaid diagram
+fn is_mermaid(info: &str) -> bool {
+    info.split_whitespace().next() == Some("mermaid")
+}
+
+/// Whether an HTML block starts with a Leptos component, named in PascalCase unlike HTML tags
+fn is_component(html: &str) -> bool {
+    html.strip_prefix('<')
+        .and_then(|name| name.chars().next())
+        .is_some_and(|first| first.is_ascii_uppercase())
+}
+
+/// Text safe in an HTML attribute
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
 }
@@ -0,0 +1,177 @@
+use insta::assert_snapshot;
+
+use super::*;
+
+/// Folder of the test posts, holding their images
+const DIR: &str = "src/markdown_parser/fixtures";
+
+fn markdown() -> Markdown<'static> {
+    let public_dir = std::env::temp_dir().join("markdown_tests_public");
+    Markdown::new(vec!["Counter".to_string()], public_dir)
+}
+
+/// Post with the given body, under a minimal front matter
+pub(crate) fn parse(body: &str) -> Result<Parsed, String> {
+    let md_text = format!("---\ntitle: Test\ndate: 2025-05-29\n---\n{body}");
+    markdown().parse_markdown(&md_text, Path::new(DIR))
+}
+
+/// Generated `view!` tokens for a post with the given body
+fn render(body: &str) -> String {
+    parse(body).unwrap().body.to_string()
+}
+
+#[test]
+fn headings_and_paragraphs() {
+    assert_snapshot!(render(
+        "# One\n\n## Two\n\nSome text\nover two lines\n\n---\n"
+    ));
+}
+
+#[test]
+fn inline_formatting() {
+    assert_snapshot!(render("*em* **strong** ~~struck~~ `code` line  \nbreak"));
+}
+
+#[test]
+fn links_and_images() {
+    assert_snapshot!(render(
+        "[plain](/blog) [titled](https://leptos.dev \"Leptos\") https://autolink.dev\n\n![An *image*](cat.png \"Cat\")"
+    ));
+}
+
+#[test]
+fn missing_image() {
+    let err = parse("![Dog](dog.png)\n").err().unwrap();
+    assert!(
+        err.starts_with("image `dog.png` not found at `src/markdown_parser/fixtures/dog.png`"),
+        "{err}"
+    );
+}
+
+#[test]
+fn lists() {
+    assert_snapshot!(render(
+        "- one\n- two\n\n3. three\n4. four\n\n- loose\n\n- list\n"
+    ));
+}
+
+#[test]
+fn task_list() {
+    assert_snapshot!(render("- [x] done\n- [ ] todo\n"));
+}
+
+#[test]
+fn block_quote_and_alert() {
+    assert_snapshot!(render("> quote\n\n> [!WARNING]\n> Careful\n"));
+}
+
+#[test]
+fn code_blocks() {
+    assert_snapshot!(render("```rust\nfn main() {}\n```\n\n    indented\n"));
+}
+
+#[test]
+fn highlighted_lines() {
+    assert_snapshot!(render(
+        "```rust {2,4-5} showLineNumbers\n/* a\nb */\nlet a = 1;\nlet b = 2;\nlet c = 3;\n```\n"
+    ));
+}
+
+#[test]
+fn invalid_line_range() {
+    let err = parse("```rust {3-1}\n```\n").err();
+    assert_eq!(
+        err.as_deref(),
+        Some("invalid line range `3-1` in code block `rust {3-1}`")
+    );
+}
+
+#[test]
+fn table() {
+    assert_snapshot!(render(
+        "| left | center | right | none |\n|:--|:-:|--:|---|\n| 1 | 2 | 3 | 4 |\n"
+    ));
+}
+
+#[test]
+fn footnotes() {
+    assert_snapshot!(render(
+        "First[^a] and again[^a], second[^b].\n\n[^a]: Note A.\n[^b]: Note B.\n"
+    ));
+}
+
+#[test]
+fn html() {
+    assert_snapshot!(render(
+        "<!-- hidden -->\n\n<div class=\"box\">block</div>\n\nPress <kbd>Ctrl</kbd> **now**\n"
+    ));
+}
+
+#[test]
+fn html_with_image_and_math() {
+    assert_snapshot!(render(
+        "Press <kbd>x</kbd> for ![A \"cat\"](cat.png \"Cat\") and $x^2$\n\n| <b>Key</b> |\n| --- |\n| ![cat](cat.png) |\n"
+    ));
+}
+
+#[test]
+fn components() {
+    assert_snapshot!(render(
+        "<Counter initial=3 />\n\n```leptos\n<Counter initial={1 + 2}>\n    <p>\"Inside\"</p>\n</Counter>\n```\n"
+    ));
+}
+
+#[test]
+fn unknown_component() {
+    let err = parse("<Chart />\n").err();
+    assert_eq!(
+        err.as_deref(),
+        Some("unknown component `Chart`, add it to `components = [...]` of `include_md!`")
+    );
+}
+
+#[test]
+fn table_of_contents() {
+    let parsed = parse("## Setup\n### Install\n## Setup\n#### Deep `code`\n## !!!\n").unwrap();
+    assert_eq!(
+        parsed.toc.ids(),
+        ["setup", "install", "setup-1", "deep-code", "section"]
+    );
+    assert_snapshot!(parsed.toc.list().to_string());
+}
+
+#[test]
+fn excerpt() {
+    let parsed =
+        parse("# Title\n\n```rust\nfn main() {}\n```\n\nFirst *real*\nparagraph.\n\nSecond one.\n")
+            .unwrap();
+    assert_eq!(parsed.excerpt.as_deref(), Some("First real paragraph."));
+}
+
+#[test]
+fn word_count() {
+    let parsed =
+        parse("# Two words\n\nThree `more` words.\n\n```rust\nfn left_out() {}\n```\n").unwrap();
+    assert_eq!(parsed.words, 5);
+}
+
+#[test]
+fn math() {
+    assert_snapshot!(render(
+        "Inline $e^{i\\pi} + 1 = 0$ math.\n\n$$\n\\frac{a}{b}\n$$\n"
+    ));
+}
+
+#[test]
+fn invalid_math() {
+    let err = parse("$\\frac{a}{$\n").err();
+    assert!(err.is_some_and(|err| err.starts_with("invalid math `\\frac{a}{`:")));
+}
+
+#[test]
+fn mermaid() {
+    let parsed = parse("```mermaid\ngraph TD\n  A --> B\n```\n").unwrap();
+    assert!(parsed.mermaid);
+    assert_snapshot!(parsed.body.to_string());
+}
@@ -0,0 +1,34 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::args::Args;
+
+/// `{Prefix}Mermaid`, drawing the ```mermaid diagrams of the page once it's in the browser
+///
+/// Only the articles with a diagram include it, the others load nothing.
+pub fn island(args: &Args) -> TokenStream {
+    let mermaid = args.component("Mermaid");
+    let script = format!(
+        "import mermaid from {:?};\n\
+         mermaid.initialize({{ startOnLoad: false }});\n\
+         await mermaid.run({{ querySelector: \"pre.mermaid\" }});",
+        args.mermaid_url
+    );
+    quote! {
+        #[island]
+        fn #mermaid() -> impl IntoView {
+            // A module script runs when added, also after a client-side navigation
+            Effect::new(move |_| {
+                let document = document();
+                let Ok(script) = document.create_element("script") else {
+                    return;
+                };
+                let _ = script.set_attribute("type", "module");
+                script.set_text_content(Some(#script));
+                if let Some(body) = document.body() {
+                    let _ = body.append_child(&script);
+                }
+            });
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::args::Args;
+use crate::description::Description;
+
+/// `{Prefix}SiteUrl`, the context the server provides for the absolute links of the meta tags
+pub fn site_url(args: &Args) -> TokenStream {
+    let site_url = args.component("SiteUrl");
+    quote! {
+        /// Public URL of the site, e.g. `https://example.com`
+        ///
+        /// Canonical and OpenGraph links are relative to the page when it isn't provided.
+        #[derive(Clone)]
+        pub struct #site_url(pub String);
+    }
+}
+
+/// Title, description, canonical link and OpenGraph/Twitter card tags of an article
+pub fn tags(args: &Args, description: &Description) -> TokenStream {
+    let site_url = args.component("SiteUrl");
+    let title = &description.title;
+    let href = description.href();
+    let summary = description.meta_description();
+    let published = description.date.to_rfc3339();
+
+    quote! {
+        {
+            use leptos_meta::{Link, Meta, Title};
+
+            let url = use_context::<#site_url>().map_or_else(
+                || #href.to_string(),
+                |site_url| format!("{}{}", site_url.0.trim_end_matches('/'), #href),
+            );
+            view! {
+                <Title text=#title />
+                <Meta name="description" content=#summary />
+                <Link rel="canonical" href=url.clone() />
+                <Meta property="og:type" content="article" />
+                <Meta property="og:title" content=#title />
+                <Meta property="og:description" content=#summary />
+                <Meta property="og:url" content=url />
+                <Meta property="article:published_time" content=#published />
+                <Meta name="twitter:card" content="summary" />
+                <Meta name="twitter:title" content=#title />
+                <Meta name="twitter:description" content=#summary />
+            }
+        }
+    }
+}
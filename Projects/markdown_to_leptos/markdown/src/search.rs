@@ -0,0 +1,252 @@
+use std::collections::BTreeMap;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::args::Args;
+use crate::description::Description;
+
+/// Score of a word in the title, a heading and the text
+const WEIGHTS: [u32; 3] = [5, 3, 1];
+
+/// Lowercase words of the text, the single letters left out
+///
+/// Must split like `{prefix}_words` in the generated search, so queries meet the index.
+fn terms(text: &str) -> impl Iterator<Item = String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() > 1)
+        .map(str::to_lowercase)
+}
+
+/// `{prefix}SearchPage` at `search?q=`, with the index of every post built at compile time
+///
+/// Returns the components and the route, relative to the collection route.
+pub fn page(args: &Args, descriptions: &[Description]) -> (Vec<TokenStream>, TokenStream) {
+    // Term -> (post, score) postings, sorted by term
+    let mut index = BTreeMap::<String, BTreeMap<u32, u32>>::new();
+    for (post, description) in descriptions.iter().enumerate() {
+        let headings = description.headings.join(" ");
+        let fields = [&description.title, &headings, &description.text];
+        for (field, weight) in fields.into_iter().zip(WEIGHTS) {
+            for term in terms(field) {
+                *index
+                    .entry(term)
+                    .or_default()
+                    .entry(post as u32)
+                    .or_default() += weight;
+            }
+        }
+    }
+    let index = index.iter().map(|(term, postings)| {
+        let postings = postings.iter().map(|(post, score)| quote!((#post, #score)));
+        quote!((#term, &[#(#postings),*]))
+    });
+    let texts = descriptions.iter().map(|description| {
+        let title = &description.title;
+        let summary = description.meta_description();
+        quote!((#title, #summary))
+    });
+
+    let search_page = args.component("SearchPage");
+    let search = args.component("Search");
+    let entries = args.constant("ENTRIES");
+    let posts = args.constant("SEARCH_POSTS");
+    let search_index = args.constant("SEARCH_INDEX");
+    let words = args.function("words");
+    let snippet = args.function("snippet");
+    let action = args.href("search");
+
+    let fn_list = vec![
+        quote! {
+            /// Title and description of every entry, as the results show them
+            static #posts: &[(&str, &str)] = &[#(#texts),*];
+
+            /// Posts and scores of every word, sorted by word
+            static #search_index: &[(&str, &[(u32, u32)])] = &[#(#index),*];
+
+            /// Byte ranges of the words of the text, the single letters left out
+            fn #words(text: &str) -> Vec<(usize, usize)> {
+                let mut words = vec![];
+                let mut start = None;
+                for (index, c) in text.char_indices().chain([(text.len(), ' ')]) {
+                    match (c.is_alphanumeric(), start) {
+                        (true, None) => start = Some(index),
+                        (false, Some(begin)) => {
+                            if text[begin..index].chars().count() > 1 {
+                                words.push((begin, index));
+                            }
+                            start = None;
+                        }
+                        _ => {}
+                    }
+                }
+                words
+            }
+
+            /// About 30 words of the description around the first match, as (text, matched) parts
+            fn #snippet(text: &str, terms: &[String]) -> Vec<(String, bool)> {
+                let words = #words(text);
+                let matches = |&(start, end): &(usize, usize)| {
+                    let word = text[start..end].to_lowercase();
+                    terms.iter().any(|term| word.starts_with(term.as_str()))
+                };
+                let first = words.iter().position(matches).unwrap_or(0);
+                let (from, to) = (first.saturating_sub(8), (first + 24).min(words.len()));
+                let shown = &words[from..to];
+                let (Some(start), Some(end)) = (shown.first(), shown.last()) else {
+                    return vec![];
+                };
+                // The whole text shows its punctuation, a cut one an ellipsis
+                let (start, end) = (
+                    if from == 0 { 0 } else { start.0 },
+                    if to == words.len() { text.len() } else { end.1 },
+                );
+
+                let mut parts = vec![];
+                if start > 0 {
+                    parts.push(("…".to_string(), false));
+                }
+                let mut cursor = start;
+                for word in shown.iter().filter(|word| matches(word)) {
+                    parts.push((text[cursor..word.0].to_string(), false));
+                    parts.push((text[word.0..word.1].to_string(), true));
+                    cursor = word.1;
+                }
+                parts.push((text[cursor..end].to_string(), false));
+                if end < text.len() {
+                    parts.push(("…".to_string(), false));
+                }
+                parts
+            }
+        },
+        quote! {
+            /// Search box ranking the posts as the query is typed, all in the browser
+            ///
+            /// `posts` are the title, link and description of every entry, `None` when hidden.
+            #[island]
+            fn #search(
+                query: String,
+                posts: Vec<Option<(String, String, String)>>,
+                index: Vec<(String, Vec<(u32, u32)>)>,
+            ) -> impl IntoView {
+                let (query, set_query) = signal(query);
+                let posts = std::sync::Arc::new(posts);
+
+                let results = move || {
+                    let text = query.read();
+                    let terms: Vec<String> = #words(&text)
+                        .into_iter()
+                        .map(|(start, end)| text[start..end].to_lowercase())
+                        .collect();
+                    if terms.is_empty() {
+                        return None;
+                    }
+
+                    // Every term must match, the last one as a prefix while it's being typed
+                    let mut scores: Vec<Option<u32>> = posts.iter().map(|post| post.as_ref().map(|_| 0)).collect();
+                    for (position, term) in terms.iter().enumerate() {
+                        let last = position + 1 == terms.len();
+                        let mut found = vec![0; posts.len()];
+                        for (word, postings) in &index {
+                            if word == term || (last && word.starts_with(term.as_str())) {
+                                for &(post, score) in postings {
+                                    found[post as usize] += score;
+                                }
+                            }
+                        }
+                        for (score, found) in scores.iter_mut().zip(found) {
+                            *score = score.filter(|_| found > 0).map(|score| score + found);
+                        }
+                    }
+
+                    let mut ranked: Vec<(u32, usize)> = scores
+                        .into_iter()
+                        .enumerate()
+                        .filter_map(|(post, score)| Some((score?, post)))
+                        .collect();
+                    ranked.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+                    let results = ranked
+                        .into_iter()
+                        .filter_map(|(_, post)| posts[post].clone())
+                        .map(|(title, href, text)| {
+                            let parts = #snippet(&text, &terms)
+                                .into_iter()
+                                .map(|(part, matched)| {
+                                    if matched {
+                                        view! { <mark>{part}</mark> }.into_any()
+                                    } else {
+                                        part.into_any()
+                                    }
+                                })
+                                .collect_view();
+                            view! {
+                                <li>
+                                    <a href=href>{title}</a>
+                                    <p class="snippet">{parts}</p>
+                                </li>
+                            }
+                        })
+                        .collect::<Vec<_>>();
+                    Some(if results.is_empty() {
+                        view! { <p>"No posts found"</p> }.into_any()
+                    } else {
+                        view! { <ul class="results">{results}</ul> }.into_any()
+                    })
+                };
+
+                view! {
+                    <form class="search" action=#action method="get">
+                        <input
+                            type="search"
+                            name="q"
+                            placeholder="Search posts"
+                            prop:value=query
+                            on:input=move |event| set_query.set(event_target_value(&event))
+                        />
+                    </form>
+                    {results}
+                }
+            }
+        },
+        quote! {
+            /// Search page, passing the index of the visible posts to the search island
+            #[component]
+            pub fn #search_page() -> impl IntoView {
+                let query = leptos_router::hooks::use_query_map()
+                    .get_untracked()
+                    .get("q")
+                    .unwrap_or_default();
+                let visible = #entries.iter().map(|entry| entry.is_visible()).collect::<Vec<_>>();
+                let posts = #posts
+                    .iter()
+                    .zip(#entries)
+                    .zip(&visible)
+                    .map(|((&(title, summary), entry), &visible)| {
+                        visible.then(|| (title.to_string(), entry.href.to_string(), summary.to_string()))
+                    })
+                    .collect::<Vec<_>>();
+                // Hidden posts are left out, so their words don't reach the browser
+                let index = #search_index
+                    .iter()
+                    .filter_map(|&(term, postings)| {
+                        let postings = postings
+                            .iter()
+                            .copied()
+                            .filter(|&(post, _)| visible[post as usize])
+                            .collect::<Vec<_>>();
+                        (!postings.is_empty()).then(|| (term.to_string(), postings))
+                    })
+                    .collect::<Vec<_>>();
+
+                view! {
+                    <h1>"Search"</h1>
+                    <#search query=query posts=posts index=index />
+                }
+            }
+        },
+    ];
+
+    let route = quote!(<Route path=path!("search") view=#search_page />);
+    (fn_list, route)
+}
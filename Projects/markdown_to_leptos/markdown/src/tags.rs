@@ -0,0 +1,123 @@
+use convert_case::{Case, Casing};
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::args::Args;
+use crate::description::Description;
+use crate::dirs;
+use crate::entries;
+use crate::slug::slugify;
+
+/// Link to the posts with the tag, in the collection at `route`
+pub fn href(route: &str, tag: &str) -> String {
+    format!("/{route}/tags/{}", slugify(tag))
+}
+
+/// Links to every tag, e.g. in the article header
+pub fn links(route: &str, tags: &[String]) -> TokenStream {
+    let links = tags.iter().map(|tag| {
+        let href = href(route, tag);
+        quote!(<A href=#href>#tag</A>)
+    });
+    quote!(#(#links)*)
+}
+
+/// Posts sharing a tag, newest first
+struct TagGroup<'a> {
+    /// Spelling of the first post using the tag
+    name: &'a str,
+    slug: String,
+    posts: Vec<&'a Description>,
+}
+
+/// Groups posts by tag slug, so `Rust` and `rust` are one tag
+fn group(descriptions: &[Description]) -> Vec<TagGroup<'_>> {
+    let mut groups = Vec::<TagGroup>::new();
+    for description in descriptions {
+        for tag in &description.tags {
+            let slug = slugify(tag);
+            match groups.iter_mut().find(|group| group.slug == slug) {
+                Some(group) => group.posts.push(description),
+                None => groups.push(TagGroup {
+                    name: tag,
+                    slug,
+                    posts: vec![description],
+                }),
+            }
+        }
+    }
+
+    groups.sort_by_key(|group| group.name.to_lowercase());
+    groups
+}
+
+/// `{prefix}TagsPage` listing every tag, and one page with its posts per tag
+///
+/// Returns the components and their routes, relative to the collection route.
+pub fn pages(args: &Args, descriptions: &[Description]) -> (Vec<TokenStream>, Vec<TokenStream>) {
+    let groups = group(descriptions);
+    let tags_page = args.component("TagsPage");
+
+    let mut fn_list = vec![];
+    let mut route_list = vec![];
+
+    // Tags whose posts are all hidden at request time aren't listed
+    let index = groups.iter().map(|group| {
+        let name = group.name;
+        let href = href(&args.route, name);
+        let count = entries::count(args, descriptions, group.posts.iter().copied());
+        quote! {
+            {
+                let count = #count;
+                (count > 0).then(|| view! {
+                    <li>
+                        <A href=#href>#name <span class="count">{count}</span></A>
+                    </li>
+                })
+            }
+        }
+    });
+    fn_list.push(quote! {
+        #[component]
+        pub fn #tags_page() -> impl IntoView {
+            view! {
+                <h1>"Tags"</h1>
+                <ul class="tag-list">
+                    #(#index)*
+                </ul>
+            }
+            .into_any()
+        }
+    });
+    route_list.push(quote!(<Route path=path!("tags") view=#tags_page />));
+
+    let mut fn_names = Vec::new();
+    for group in &groups {
+        // `dota2` -> BlogTagDota2, numbered when slugs like `c-sharp` and `csharp` meet
+        let fn_name = dirs::unique(
+            &mut fn_names,
+            format!(
+                "{}Tag{}",
+                args.component_prefix,
+                group.slug.to_case(Case::Pascal)
+            ),
+        );
+        let name = group.name;
+        let cards = entries::cards(args, descriptions, group.posts.iter().copied());
+        let route_path = format!("tags/{}", group.slug);
+
+        fn_list.push(quote! {
+            #[component]
+            fn #fn_name() -> impl IntoView {
+                view! {
+                    <h1>"Tag: " #name</h1>
+                    #cards
+                }
+                .into_any()
+            }
+        });
+        route_list.push(quote!(<Route path=path!(#route_path) view=#fn_name />));
+    }
+
+    (fn_list, route_list)
+}
@@ -0,0 +1,176 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+
+use crate::args::Args;
+use crate::slug::slugify;
+
+/// Heading of an article, linked from the table of contents
+pub struct Heading {
+    pub level: u8,
+    pub title: String,
+    pub id: String,
+}
+
+/// Headings of an article in document order, with unique anchor ids
+#[derive(Default)]
+pub struct Toc {
+    headings: Vec<Heading>,
+}
+
+impl Toc {
+    /// Adds a heading and returns its id, numbered when the slug is taken
+    ///
+    /// `Setup`, `Setup` -> `setup`, `setup-1`
+    pub fn add(&mut self, level: u8, title: String) -> String {
+        let mut slug = slugify(&title);
+        if slug.is_empty() {
+            slug = "section".to_string();
+        }
+
+        let mut id = slug.clone();
+        let mut count = 0;
+        while self.headings.iter().any(|heading| heading.id == id) {
+            count += 1;
+            id = format!("{slug}-{count}");
+        }
+
+        self.headings.push(Heading {
+            level,
+            title,
+            id: id.clone(),
+        });
+        id
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.listed().next().is_none()
+    }
+
+    /// Text of every heading
+    pub fn titles(&self) -> Vec<String> {
+        self.headings
+            .iter()
+            .map(|heading| heading.title.clone())
+            .collect()
+    }
+
+    /// Anchor ids of every heading, the title included
+    pub fn anchors(&self) -> Vec<String> {
+        self.headings
+            .iter()
+            .map(|heading| heading.id.clone())
+            .collect()
+    }
+
+    /// Anchor ids of the listed headings
+    pub fn ids(&self) -> Vec<&str> {
+        self.listed().map(|heading| heading.id.as_str()).collect()
+    }
+
+    /// Headings below the article title, which is the only H1
+    fn listed(&self) -> impl Iterator<Item = &Heading> {
+        self.headings.iter().filter(|heading| heading.level > 1)
+    }
+
+    /// Nested list of links to the headings, H2 to H6
+    pub fn list(&self) -> TokenStream {
+        let headings: Vec<&Heading> = self.listed().collect();
+        nest(&headings)
+    }
+}
+
+/// `<ul>` of the headings, each one holding the deeper headings that follow it
+fn nest(headings: &[&Heading]) -> TokenStream {
+    let mut items = vec![];
+    let mut rest = headings;
+    while let Some((heading, after)) = rest.split_first() {
+        let end = after
+            .iter()
+            .position(|next| next.level <= heading.level)
+            .unwrap_or(after.len());
+        let (children, next) = after.split_at(end);
+
+        let href = format!("#{}", heading.id);
+        let title = &heading.title;
+        let children = (!children.is_empty()).then(|| nest(children));
+        items.push(quote! {
+            <li>
+                <a href=#href>#title</a>
+                #children
+            </li>
+        });
+        rest = next;
+    }
+    quote!(<ul>#(#items)*</ul>)
+}
+
+/// Table of contents component of an article, `None` when it has no sections
+pub fn component(args: &Args, toc_name: &Ident, toc: &Toc) -> Option<TokenStream> {
+    if toc.is_empty() {
+        return None;
+    }
+
+    let list = toc.list();
+    let nav = quote! {
+        <nav class="toc">
+            <p class="toc-title">"Contents"</p>
+            #list
+        </nav>
+    };
+    let nav = if args.toc_highlight {
+        let highlight = args.component("TocHighlight");
+        let ids = toc.ids();
+        quote!(<#highlight ids=vec![#(#ids.to_string()),*]>#nav</#highlight>)
+    } else {
+        nav
+    };
+
+    Some(quote! {
+        #[component]
+        pub fn #toc_name() -> impl IntoView {
+            view! { #nav }
+        }
+    })
+}
+
+/// `{prefix}TocHighlight`, marking the link of the section being read with `aria-current`
+pub fn highlight_component(args: &Args) -> TokenStream {
+    let highlight = args.component("TocHighlight");
+    quote! {
+        #[island]
+        fn #highlight(ids: Vec<String>, children: Children) -> impl IntoView {
+            Effect::new(move |_| {
+                use leptos::wasm_bindgen::JsCast;
+
+                let ids = ids.clone();
+                let update = move || {
+                    let document = document();
+                    let scroll = window().scroll_y().unwrap_or_default();
+                    // Last heading above the top of the window, leaving some room
+                    let current = ids.iter().rev().find(|id| {
+                        document
+                            .get_element_by_id(id)
+                            .and_then(|heading| heading.dyn_into::<leptos::web_sys::HtmlElement>().ok())
+                            .is_some_and(|heading| f64::from(heading.offset_top()) <= scroll + 80.0)
+                    });
+                    for id in &ids {
+                        let selector = format!(".toc a[href=\"#{id}\"]");
+                        if let Ok(Some(link)) = document.query_selector(&selector) {
+                            let _ = if Some(id) == current {
+                                link.set_attribute("aria-current", "location")
+                            } else {
+                                link.remove_attribute("aria-current")
+                            };
+                        }
+                    }
+                };
+                update();
+                // Islands hydrate again after client-side navigation, each with its own listener
+                let listener = window_event_listener(leptos::ev::scroll, move |_| update());
+                on_cleanup(move || listener.remove());
+            });
+
+            children()
+        }
+    }
+}
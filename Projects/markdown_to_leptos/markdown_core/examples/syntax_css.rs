@@ -0,0 +1,8 @@
+//! Prints the stylesheet of the highlighted code blocks
+//!
+//! `cargo run -p markdown_core --example syntax_css > style/_syntax.scss`
+
+fn main() {
+    let css = markdown_core::highlight::theme_css().expect("Failed to generate the theme CSS");
+    print!("{css}");
+}
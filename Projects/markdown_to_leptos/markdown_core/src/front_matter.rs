@@ -0,0 +1,264 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use comrak::nodes::{AstNode, NodeValue};
+use serde::Deserialize;
+
+use crate::slug::slugify;
+
+/// Syntax of the front matter block, chosen by its delimiter
+#[derive(Clone, Copy)]
+pub enum Format {
+    /// Between `---` lines
+    Yaml,
+    /// Between `+++` lines
+    Toml,
+}
+
+impl Format {
+    pub fn detect(md_text: &str) -> Self {
+        if md_text.starts_with("+++") {
+            Format::Toml
+        } else {
+            Format::Yaml
+        }
+    }
+
+    pub fn delimiter(self) -> &'static str {
+        match self {
+            Format::Yaml => "---",
+            Format::Toml => "+++",
+        }
+    }
+}
+
+/// Metadata at the top of every post
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FrontMatter {
+    pub title: String,
+    #[serde(deserialize_with = "deserialize_date")]
+    pub date: DateTime<Utc>,
+    #[serde(default, deserialize_with = "deserialize_tags")]
+    pub tags: Vec<String>,
+    pub summary: Option<String>,
+    /// Unfinished post
+    #[serde(default)]
+    pub draft: bool,
+    /// Route path, the file name when `None`
+    pub slug: Option<String>,
+}
+
+impl FrontMatter {
+    /// Front matter of a document parsed with `format`'s delimiter, which must start with one
+    pub fn from_document<'a>(root: &'a AstNode<'a>, format: Format) -> Result<Self, String> {
+        let raw = root
+            .first_child()
+            .and_then(|node| match &node.data.borrow().value {
+                NodeValue::FrontMatter(front_matter) => Some(front_matter.clone()),
+                _ => None,
+            })
+            .ok_or_else(|| {
+                format!(
+                    "missing front matter, expected a `{}` block with title and date",
+                    format.delimiter()
+                )
+            })?;
+        Self::parse(&raw, format)
+    }
+
+    /// Parses the raw block from comrak's `FrontMatter` node, delimiters included
+    pub fn parse(raw: &str, format: Format) -> Result<Self, String> {
+        let delimiter = format.delimiter();
+        let content = raw
+            .trim()
+            .strip_prefix(delimiter)
+            .and_then(|content| content.strip_suffix(delimiter))
+            .unwrap_or_default();
+
+        let front_matter: Self = match format {
+            Format::Yaml => serde_yaml::from_str(content).map_err(|err| err.to_string())?,
+            Format::Toml => toml::from_str(content).map_err(|err| err.to_string())?,
+        };
+
+        if let Some(slug) = &front_matter.slug {
+            let valid = !slug.is_empty()
+                && slug
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid {
+                return Err(format!(
+                    "invalid slug `{slug}`, expected ASCII letters, digits, `-` or `_`"
+                ));
+            }
+        }
+
+        // Tags become routes
+        if let Some(tag) = front_matter.tags.iter().find(|tag| slugify(tag).is_empty()) {
+            return Err(format!("tag `{tag}` needs at least one letter or digit"));
+        }
+
+        Ok(front_matter)
+    }
+}
+
+/// Tags written as a list, or as one comma-separated string
+#[derive(Deserialize)]
+#[serde(untagged, expecting = "a list of tags or a comma-separated string")]
+enum RawTags {
+    List(Vec<String>),
+    Text(String),
+}
+
+fn deserialize_tags<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let tags = match RawTags::deserialize(deserializer)? {
+        RawTags::List(tags) => tags,
+        RawTags::Text(text) => text.split(',').map(str::to_string).collect(),
+    };
+
+    let mut unique = Vec::<String>::new();
+    for tag in tags
+        .iter()
+        .map(|tag| tag.trim())
+        .filter(|tag| !tag.is_empty())
+    {
+        if !unique.iter().any(|t| t == tag) {
+            unique.push(tag.to_string());
+        }
+    }
+    Ok(unique)
+}
+
+/// A date written as text, or a TOML date
+#[derive(Deserialize)]
+#[serde(
+    untagged,
+    expecting = "a date such as `2025-05-29` or `2025-05-29 12:00:00`"
+)]
+enum RawDate {
+    Text(String),
+    Toml(toml::value::Datetime),
+}
+
+fn deserialize_date<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let text = match RawDate::deserialize(deserializer)? {
+        RawDate::Text(text) => text,
+        RawDate::Toml(datetime) => datetime.to_string(),
+    };
+
+    parse_date(&text).ok_or_else(|| {
+        serde::de::Error::custom(format!(
+            "invalid date `{text}`, expected `YYYY-MM-DD`, `YYYY-MM-DD HH:MM:SS` or RFC 3339"
+        ))
+    })
+}
+
+fn parse_date(text: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(text) {
+        return Some(date.with_timezone(&Utc));
+    }
+
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+        .map(|naive| naive.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yaml(fields: &str) -> Result<FrontMatter, String> {
+        FrontMatter::parse(&format!("---\n{fields}\n---\n"), Format::Yaml)
+    }
+
+    fn error(fields: &str) -> String {
+        yaml(fields).err().unwrap_or_default()
+    }
+
+    #[test]
+    fn yaml_fields() {
+        let front_matter = yaml(
+            "title: Intro\ndate: 2025-05-29 12:30:00\ntags: [Rust, Leptos]\nsummary: Hi\ndraft: true\nslug: my-intro",
+        )
+        .unwrap();
+        assert_eq!(front_matter.title, "Intro");
+        assert_eq!(front_matter.date.to_rfc3339(), "2025-05-29T12:30:00+00:00");
+        assert_eq!(front_matter.tags, ["Rust", "Leptos"]);
+        assert_eq!(front_matter.summary.as_deref(), Some("Hi"));
+        assert!(front_matter.draft);
+        assert_eq!(front_matter.slug.as_deref(), Some("my-intro"));
+    }
+
+    #[test]
+    fn toml_fields() {
+        let raw = "+++\ntitle = \"Intro\"\ndate = 2025-05-29\ntags = [\"Rust\"]\n+++\n";
+        let front_matter = FrontMatter::parse(raw, Format::Toml).unwrap();
+        assert_eq!(front_matter.title, "Intro");
+        assert_eq!(front_matter.date.to_rfc3339(), "2025-05-29T00:00:00+00:00");
+        assert_eq!(front_matter.tags, ["Rust"]);
+        assert!(!front_matter.draft);
+    }
+
+    #[test]
+    fn comma_separated_tags() {
+        let front_matter =
+            yaml("title: Intro\ndate: 2025-05-29\ntags: Rust, leptos ,, Rust").unwrap();
+        assert_eq!(front_matter.tags, ["Rust", "leptos"]);
+    }
+
+    #[test]
+    fn missing_date() {
+        assert!(error("title: Intro").contains("missing field `date`"));
+    }
+
+    #[test]
+    fn invalid_date() {
+        assert!(error("title: Intro\ndate: 29/05/2025").contains(
+            "invalid date `29/05/2025`, expected `YYYY-MM-DD`, `YYYY-MM-DD HH:MM:SS` or RFC 3339"
+        ));
+    }
+
+    #[test]
+    fn unknown_field() {
+        assert!(
+            error("title: Intro\ndate: 2025-05-29\nauthor: Me").contains("unknown field `author`")
+        );
+    }
+
+    #[test]
+    fn invalid_slug() {
+        assert_eq!(
+            error("title: Intro\ndate: 2025-05-29\nslug: my intro"),
+            "invalid slug `my intro`, expected ASCII letters, digits, `-` or `_`"
+        );
+    }
+
+    #[test]
+    fn invalid_tag() {
+        assert_eq!(
+            error("title: Intro\ndate: 2025-05-29\ntags: [\"!!\"]"),
+            "tag `!!` needs at least one letter or digit"
+        );
+    }
+
+    #[test]
+    fn missing_front_matter() {
+        let arena = comrak::Arena::new();
+        let root = comrak::parse_document(&arena, "# Intro\n", &crate::options());
+        let err = FrontMatter::from_document(root, Format::Toml).err();
+        assert_eq!(
+            err.as_deref(),
+            Some("missing front matter, expected a `+++` block with title and date")
+        );
+    }
+}
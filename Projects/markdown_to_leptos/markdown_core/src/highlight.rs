@@ -0,0 +1,176 @@
+use std::collections::BTreeSet;
+
+use syntect::highlighting::ThemeSet;
+use syntect::html::{ClassStyle, css_for_theme_with_class_style, line_tokens_to_classed_spans};
+use syntect::parsing::{ParseState, ScopeStack, SyntaxSet};
+use syntect::util::LinesWithEndings;
+
+/// Theme of the highlighted code blocks
+const THEME: &str = "InspiredGitHub";
+/// Class prefix of the highlighted spans
+const PREFIX: &str = "hl-";
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: PREFIX };
+
+/// Info string of a fenced code block, e.g. `rust {3,5-7} showLineNumbers`
+pub struct CodeInfo {
+    pub language: Option<String>,
+    /// 1-based numbers of the lines to highlight
+    pub highlighted: BTreeSet<usize>,
+    pub line_numbers: bool,
+}
+
+impl CodeInfo {
+    pub fn parse(info: &str) -> Result<Self, String> {
+        let (words, ranges) = match info.split_once('{') {
+            Some((before, rest)) => {
+                let (ranges, after) = rest
+                    .split_once('}')
+                    .ok_or_else(|| format!("unclosed line range in code block `{info}`"))?;
+                (format!("{before} {after}"), ranges)
+            }
+            None => (info.to_string(), ""),
+        };
+
+        let mut highlighted = BTreeSet::new();
+        for range in ranges.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+            let invalid = || format!("invalid line range `{range}` in code block `{info}`");
+            let (start, end) = match range.split_once('-') {
+                Some((start, end)) => (start.trim(), end.trim()),
+                None => (range, range),
+            };
+            let start: usize = start.parse().map_err(|_| invalid())?;
+            let end: usize = end.parse().map_err(|_| invalid())?;
+            if start == 0 || start > end {
+                return Err(invalid());
+            }
+            highlighted.extend(start..=end);
+        }
+
+        let mut language = None;
+        let mut line_numbers = false;
+        for word in words.split_whitespace() {
+            match word {
+                "showLineNumbers" => line_numbers = true,
+                word if language.is_none() => language = Some(word.to_string()),
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            language,
+            highlighted,
+            line_numbers,
+        })
+    }
+
+    /// Classes of the `<pre>`, the theme's background and the language
+    pub fn class(&self) -> String {
+        match &self.language {
+            Some(language) => format!("{PREFIX}code language-{language}"),
+            None => format!("{PREFIX}code"),
+        }
+    }
+}
+
+/// Turns code into class-based spans at compile time, so pages need no highlighting JS
+pub struct Highlighter {
+    syntaxes: SyntaxSet,
+}
+
+impl Default for Highlighter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Highlighter {
+    pub fn new() -> Self {
+        Self {
+            syntaxes: SyntaxSet::load_defaults_newlines(),
+        }
+    }
+
+    /// HTML of the code, one `<span class="line">` per line
+    pub fn highlight(&self, code: &str, info: &CodeInfo) -> Result<String, String> {
+        let syntax = info
+            .language
+            .as_deref()
+            .and_then(|language| self.syntaxes.find_syntax_by_token(language))
+            .unwrap_or_else(|| self.syntaxes.find_syntax_plain_text());
+
+        let mut parse_state = ParseState::new(syntax);
+        let mut scopes = ScopeStack::new();
+        let mut html = String::new();
+
+        for (i, line) in LinesWithEndings::from(code).enumerate() {
+            let number = i + 1;
+            let ops = parse_state
+                .parse_line(line, &self.syntaxes)
+                .map_err(|err| err.to_string())?;
+
+            // Spans still open from the previous line are closed at its end and reopened here,
+            // so every line is a self-contained element
+            let reopened = scopes
+                .as_slice()
+                .iter()
+                .map(|scope| format!("<span class=\"{}\">", classes(&scope.build_string())))
+                .collect::<String>();
+            let (mut spans, _) = line_tokens_to_classed_spans(line, &ops, CLASS_STYLE, &mut scopes)
+                .map_err(|err| err.to_string())?;
+            // Lines are blocks, the newline would add an empty one
+            if let Some(newline) = spans.rfind('\n') {
+                spans.remove(newline);
+            }
+            let closed = "</span>".repeat(scopes.len());
+
+            let class = if info.highlighted.contains(&number) {
+                "line highlighted"
+            } else {
+                "line"
+            };
+            let line_number = if info.line_numbers {
+                format!("<span class=\"line-number\">{number}</span>")
+            } else {
+                String::new()
+            };
+
+            html.push_str(&format!(
+                "<span class=\"{class}\">{line_number}{reopened}{spans}{closed}</span>"
+            ));
+        }
+
+        Ok(html)
+    }
+}
+
+/// Stylesheet of the theme, once for every code block, committed as `style/_syntax.scss`
+///
+/// Regenerate it with `cargo run -p markdown_core --example syntax_css > style/_syntax.scss`.
+pub fn theme_css() -> Result<String, String> {
+    let themes = ThemeSet::load_defaults();
+    css_for_theme_with_class_style(&themes.themes[THEME], CLASS_STYLE)
+        .map_err(|err| err.to_string())
+}
+
+/// Classes of a scope such as `source.rust`, as syntect writes them with [`CLASS_STYLE`]
+fn classes(scope: &str) -> String {
+    scope
+        .split('.')
+        .map(|atom| format!("{PREFIX}{atom}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn theme_css_is_committed() {
+        let committed = include_str!("../../style/_syntax.scss");
+        assert!(
+            theme_css().unwrap() == committed,
+            "`style/_syntax.scss` is stale, run `cargo run -p markdown_core --example syntax_css > style/_syntax.scss`"
+        );
+    }
+}
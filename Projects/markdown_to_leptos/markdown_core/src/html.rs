@@ -0,0 +1,62 @@
+use comrak::nodes::{NodeHtmlBlock, NodeValue};
+use comrak::{Arena, format_html, parse_document};
+
+use crate::front_matter::{Format, FrontMatter};
+use crate::highlight::{CodeInfo, Highlighter};
+use crate::math::{class, mathml};
+
+/// Renders a post to HTML at runtime, for the live preview while writing
+///
+/// Parses with the options, highlighting and math of `include_md!`. Embedded components and the
+/// table of contents only change with a rebuild, new mermaid diagrams with a reload.
+pub fn render(md_text: &str, highlighter: &Highlighter) -> Result<(FrontMatter, String), String> {
+    let format = Format::detect(md_text);
+    let mut options = crate::options();
+    options.extension.front_matter_delimiter = Some(format.delimiter().to_string());
+
+    let arena = Arena::new();
+    let root = parse_document(&arena, md_text, &options);
+    let front_matter = FrontMatter::from_document(root, format)?;
+
+    // Code blocks and math become the same HTML as in the compiled posts
+    for node in root.descendants() {
+        let mut data = node.data.borrow_mut();
+        let html = match &data.value {
+            NodeValue::CodeBlock(code_block)
+                if code_block.info.split_whitespace().next() == Some("mermaid") =>
+            {
+                let literal = code_block
+                    .literal
+                    .replace('&', "&amp;")
+                    .replace('<', "&lt;")
+                    .replace('>', "&gt;");
+                format!("<pre class=\"mermaid\">{literal}</pre>\n")
+            }
+            NodeValue::CodeBlock(code_block) => {
+                let info = CodeInfo::parse(&code_block.info)?;
+                let html = highlighter.highlight(&code_block.literal, &info)?;
+                format!(
+                    "<pre class=\"{}\"><code>{html}</code></pre>\n",
+                    info.class()
+                )
+            }
+            NodeValue::Math(math) => {
+                let class = class(math.display_math);
+                let html = mathml(&math.literal, math.display_math)?;
+                data.value =
+                    NodeValue::HtmlInline(format!("<span class=\"{class}\">{html}</span>"));
+                continue;
+            }
+            _ => continue,
+        };
+        data.value = NodeValue::HtmlBlock(NodeHtmlBlock {
+            block_type: 0,
+            literal: html,
+        });
+    }
+
+    let mut html = vec![];
+    format_html(root, &options, &mut html).map_err(|err| err.to_string())?;
+    let html = String::from_utf8(html).map_err(|err| err.to_string())?;
+    Ok((front_matter, html))
+}
@@ -0,0 +1,24 @@
+use comrak::Options;
+
+pub mod front_matter;
+pub mod highlight;
+pub mod html;
+pub mod math;
+pub mod slug;
+
+/// Comrak options shared by `include_md!` and the live preview
+pub fn options() -> Options<'static> {
+    let mut options = Options::default();
+    // GitHub Flavored Markdown
+    options.extension.table = true;
+    options.extension.strikethrough = true;
+    options.extension.autolink = true;
+    options.extension.tasklist = true;
+    options.extension.footnotes = true;
+    options.extension.alerts = true;
+    // `$inline$` and `$$display$$` math
+    options.extension.math_dollars = true;
+    // Posts are our own content, keep their HTML
+    options.render.unsafe_ = true;
+    options
+}
@@ -0,0 +1,23 @@
+use katex::{Opts, OutputType};
+
+/// MathML of a LaTeX formula, so pages show math with no script nor font to load
+///
+/// `display` formulas are blocks, the others flow with the text.
+pub fn mathml(latex: &str, display: bool) -> Result<String, String> {
+    let opts = Opts::builder()
+        .output_type(OutputType::Mathml)
+        .display_mode(display)
+        .build()
+        .map_err(|err| err.to_string())?;
+    katex::render_with_opts(latex, &opts)
+        .map_err(|err| format!("invalid math `{}`: {err}", latex.trim()))
+}
+
+/// Class of the `<span>` holding a formula
+pub fn class(display: bool) -> &'static str {
+    if display {
+        "math math-display"
+    } else {
+        "math math-inline"
+    }
+}
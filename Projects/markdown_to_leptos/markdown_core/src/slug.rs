@@ -0,0 +1,18 @@
+/// Lowercase letters and digits, any other run of characters becomes one `-`
+///
+/// `Dota 2 & Rust!` -> `dota-2-rust`
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    if slug.ends_with('-') {
+        slug.pop();
+    }
+    slug
+}
@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::routing::{get, MethodRouter};
+use markdown_core::highlight::Highlighter;
+use notify::{EventKind, RecursiveMode, Watcher};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// Websocket pushing every post edited in `dir`, re-rendered, as `{href}\n{html}`
+///
+/// Development only, production serves the posts compiled by `include_md!`.
+pub fn route<S>(dir: &'static str, route: &'static str) -> MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let (sender, _) = broadcast::channel(16);
+
+    // The watcher lives in its own thread, rendering on every change
+    let posts = sender.clone();
+    std::thread::spawn(move || {
+        if let Err(err) = watch(Path::new(dir), route, &posts) {
+            leptos::logging::error!("live reload of `{dir}` stopped: {err}");
+        }
+    });
+
+    get(move |upgrade: WebSocketUpgrade| {
+        let posts = sender.subscribe();
+        async move { upgrade.on_upgrade(|socket| push(socket, posts)) }
+    })
+}
+
+fn watch(dir: &Path, route: &str, posts: &broadcast::Sender<String>) -> notify::Result<()> {
+    let dir = dir.canonicalize()?;
+    let highlighter = Highlighter::new();
+
+    let (events, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(events)?;
+    watcher.watch(&dir, RecursiveMode::Recursive)?;
+    leptos::logging::log!("live reload watching {}", dir.display());
+
+    for event in receiver {
+        let event = event?;
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+            continue;
+        }
+        for path in event
+            .paths
+            .iter()
+            .filter(|path| path.extension().is_some_and(|e| e == "md"))
+        {
+            match render(&dir, route, path, &highlighter) {
+                // Nobody listening is fine
+                Ok(post) => _ = posts.send(post),
+                Err(err) => leptos::logging::error!("{}: {err}", path.display()),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `{href}\n{html}` of the post at `path`, with the route `include_md!` gives it
+fn render(
+    dir: &Path,
+    route: &str,
+    path: &Path,
+    highlighter: &Highlighter,
+) -> Result<String, String> {
+    let md_text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let (front_matter, html) = markdown_core::html::render(&md_text, highlighter)?;
+
+    let relative = path.strip_prefix(dir).map_err(|err| err.to_string())?;
+    let file_name = relative.file_stem().unwrap_or_default().to_string_lossy();
+    let mut href = PathBuf::from(format!("/{route}"));
+    if let Some(dirs) = relative.parent() {
+        href.push(dirs);
+    }
+    href.push(front_matter.slug.as_deref().unwrap_or(&file_name));
+
+    Ok(format!("{}\n{html}", href.display()))
+}
+
+async fn push(mut socket: WebSocket, mut posts: broadcast::Receiver<String>) {
+    loop {
+        let post = match posts.recv().await {
+            Ok(post) => post,
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        };
+        if socket.send(Message::Text(post.into())).await.is_err() {
+            break;
+        }
+    }
+}
@@ -1,3 +1,5 @@
+#[cfg(debug_assertions)]
+mod live_reload;
 mod server;
 
 use server::Server;
@@ -1,4 +1,6 @@
 use app::*;
+use axum::http::header::CONTENT_TYPE;
+use axum::routing::get;
 use axum::Router;
 use leptos::prelude::*;
 use leptos_axum::{generate_route_list, LeptosRoutes};
@@ -12,13 +14,40 @@ impl Server {
         let leptos_options = conf.leptos_options;
         let routes = generate_route_list(App);
 
+        // Public URL for the absolute links of the feed, sitemap and meta tags
+        let site_url = std::env::var("SITE_URL").unwrap_or_else(|_| format!("http://{addr}"));
+
         let app = Router::new()
-            .leptos_routes(&leptos_options, routes, {
-                let leptos_options = leptos_options.clone();
-                move || shell(leptos_options.clone())
+            // Built on every request, so scheduled posts show up once their date has passed
+            .route("/blog/feed.xml", {
+                let site_url = site_url.clone();
+                get(move || async move {
+                    ([(CONTENT_TYPE, "application/rss+xml")], blog_feed(&site_url))
+                })
+            })
+            .route("/sitemap.xml", {
+                let site_url = site_url.clone();
+                get(move || async move { ([(CONTENT_TYPE, "application/xml")], sitemap(&site_url)) })
             })
-            .fallback(leptos_axum::file_and_error_handler(shell))
-            .with_state(leptos_options);
+            .leptos_routes_with_context(
+                &leptos_options,
+                routes,
+                move || provide_context(BlogSiteUrl(site_url.clone())),
+                {
+                    let leptos_options = leptos_options.clone();
+                    move || shell(leptos_options.clone())
+                },
+            )
+            .fallback(leptos_axum::file_and_error_handler(shell));
+
+        // Edited posts show in the browser without waiting for a rebuild
+        #[cfg(debug_assertions)]
+        let app = app.route(
+            LIVE_RELOAD_PATH,
+            crate::live_reload::route(BLOG_DIR, BLOG_ROUTE),
+        );
+
+        let app = app.with_state(leptos_options);
 
         leptos::logging::log!("listening on http://{}", &addr);
         let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();